@@ -0,0 +1,50 @@
+//! Move generation benchmarks. Run with `cargo bench` on a native target.
+
+use chess::board::BoardState;
+use chess::fen::KnownPosition;
+use chess::piece::PieceColor;
+use chess::pos::Pos;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const POSITIONS: [KnownPosition; 3] = [
+    KnownPosition::Starting,
+    KnownPosition::Kiwipete,
+    KnownPosition::Promotion,
+];
+
+fn all_legal_moves(c: &mut Criterion) {
+    for position in POSITIONS {
+        let state = BoardState::from_fen(position.fen()).unwrap();
+        c.bench_function(&format!("all_legal_moves {position:?}"), |b| {
+            b.iter(|| black_box(&state).all_legal_moves())
+        });
+    }
+}
+
+fn is_attacked(c: &mut Criterion) {
+    for position in POSITIONS {
+        let state = BoardState::from_fen(position.fen()).unwrap();
+        c.bench_function(&format!("is_attacked {position:?}"), |b| {
+            b.iter(|| {
+                Pos::all()
+                    .filter(|pos| black_box(&state).is_attacked(*pos, PieceColor::Black))
+                    .count()
+            })
+        });
+    }
+}
+
+fn perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(10);
+    for position in POSITIONS {
+        let state = BoardState::from_fen(position.fen()).unwrap();
+        group.bench_function(format!("perft(3) {position:?}"), |b| {
+            b.iter(|| black_box(&state).perft(3))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, all_legal_moves, is_attacked, perft);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+//! Prints the perft node count of every legal move, in the format used by Stockfish's
+//! `go perft`, to cross-check move generation against other engines.
+//!
+//! Usage: `cargo run --bin perft -- "<fen>" <depth>`
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    use chess::board::BoardState;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (fen, depth) = match args.as_slice() {
+        [fen, depth] => (fen, depth),
+        _ => {
+            eprintln!("usage: perft <fen> <depth>");
+            std::process::exit(2);
+        }
+    };
+    let state = match BoardState::from_fen(fen) {
+        Ok(state) => state,
+        Err(err) => {
+            eprintln!("invalid FEN: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let depth: u32 = match depth.parse() {
+        Ok(depth) => depth,
+        Err(_) => {
+            eprintln!("invalid depth: {}", depth);
+            std::process::exit(2);
+        }
+    };
+    let divide = state.perft_divide(depth);
+    for (mv, nodes) in &divide {
+        println!("{}: {}", state.to_uci(*mv), nodes);
+    }
+    let total: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+    println!();
+    println!("Nodes searched: {}", total);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,61 @@
+use crate::piece::PieceColor;
+use crate::pos::{Pos, SquareIndex};
+
+/// Number of pieces of each color attacking every square.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttackMap {
+    white: [u8; 64],
+    black: [u8; 64],
+}
+
+impl AttackMap {
+    pub fn new() -> Self {
+        Self {
+            white: [0; 64],
+            black: [0; 64],
+        }
+    }
+
+    fn counts(&self, color: PieceColor) -> &[u8; 64] {
+        if color == PieceColor::White {
+            &self.white
+        } else {
+            &self.black
+        }
+    }
+
+    fn counts_mut(&mut self, color: PieceColor) -> &mut [u8; 64] {
+        if color == PieceColor::White {
+            &mut self.white
+        } else {
+            &mut self.black
+        }
+    }
+
+    pub fn add(&mut self, color: PieceColor, squares: impl IntoIterator<Item = Pos>) {
+        let counts = self.counts_mut(color);
+        for pos in squares {
+            counts[SquareIndex::from(pos).get()] += 1;
+        }
+    }
+
+    pub fn remove(&mut self, color: PieceColor, squares: impl IntoIterator<Item = Pos>) {
+        let counts = self.counts_mut(color);
+        for pos in squares {
+            counts[SquareIndex::from(pos).get()] -= 1;
+        }
+    }
+
+    pub fn is_attacked(&self, pos: Pos, by: PieceColor) -> bool {
+        self.counts(by)[SquareIndex::from(pos).get()] > 0
+    }
+
+    /// Bit `i` is set if the square with index `i` is attacked by `by`.
+    pub fn bitboard(&self, by: PieceColor) -> u64 {
+        self.counts(by)
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .fold(0, |bits, (i, _)| bits | 1 << i)
+    }
+}
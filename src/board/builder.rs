@@ -0,0 +1,273 @@
+use crate::board::castling::CastlingState;
+use crate::board::BoardState;
+use crate::board::Square;
+use crate::moves::CastlingSide;
+use crate::moves::MoveNumber;
+use crate::moves::Ply;
+use crate::piece::{Piece, PieceColor, PieceType};
+use crate::pos::{File, Pos, Rank, SquareIndex};
+use std::fmt;
+
+/// Why a position can't be built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionError {
+    /// Each side must have exactly one king.
+    KingCount(PieceColor),
+    PawnOnBackRank(Pos),
+    /// The side that just moved can't be left in check.
+    OpponentInCheck,
+    /// Castling rights without the king and rook on their home squares.
+    InvalidCastling(PieceColor, CastlingSide),
+    InvalidEnPassant(Pos),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KingCount(color) => write!(f, "{color} must have exactly one king"),
+            Self::PawnOnBackRank(pos) => write!(f, "pawn on the back rank at {pos}"),
+            Self::OpponentInCheck => write!(f, "the side not to move is in check"),
+            Self::InvalidCastling(color, side) => {
+                write!(f, "{color} can't have {side:?} castling rights")
+            }
+            Self::InvalidEnPassant(pos) => write!(f, "invalid en passant square {pos}"),
+        }
+    }
+}
+
+/// Sets up a position piece by piece, as an alternative to FEN.
+///
+/// Starts from an empty board with White to move and no castling rights.
+#[derive(Debug, Clone)]
+pub struct BoardBuilder {
+    squares: Vec<Square>,
+    turn: PieceColor,
+    castling: Vec<(PieceColor, CastlingSide)>,
+    en_passant: Option<Pos>,
+    halfmove_clock: Ply,
+    move_number: MoveNumber,
+}
+
+impl BoardBuilder {
+    pub fn new() -> Self {
+        Self {
+            squares: vec![Square::Empty; 64],
+            turn: PieceColor::White,
+            castling: Vec::new(),
+            en_passant: None,
+            halfmove_clock: 0,
+            move_number: 1,
+        }
+    }
+
+    pub fn piece(mut self, pos: Pos, piece: Piece) -> Self {
+        self.squares[SquareIndex::from(pos).get()] = Square::Piece(piece);
+        self
+    }
+
+    pub fn turn(mut self, color: PieceColor) -> Self {
+        self.turn = color;
+        self
+    }
+
+    /// Grants a castling right.
+    pub fn castling(mut self, color: PieceColor, side: CastlingSide) -> Self {
+        if !self.castling.contains(&(color, side)) {
+            self.castling.push((color, side));
+        }
+        self
+    }
+
+    /// The square a pawn skipped over with a double push on the previous move.
+    pub fn en_passant(mut self, pos: Pos) -> Self {
+        self.en_passant = Some(pos);
+        self
+    }
+
+    /// Plies since the last capture or pawn move, for the fifty-move rule.
+    pub fn halfmove_clock(mut self, plies: Ply) -> Self {
+        self.halfmove_clock = plies;
+        self
+    }
+
+    pub fn move_number(mut self, move_number: MoveNumber) -> Self {
+        self.move_number = move_number;
+        self
+    }
+
+    /// Removes the castling rights the king and rook placement makes impossible, instead of
+    /// failing in [`BoardBuilder::build`].
+    pub fn drop_invalid_castling(mut self) -> Self {
+        let castling = std::mem::take(&mut self.castling);
+        self.castling = castling
+            .into_iter()
+            .filter(|&(color, side)| self.is_valid_castling(color, side))
+            .collect();
+        self
+    }
+
+    /// Clears an en passant square no pawn could have just skipped, instead of failing in
+    /// [`BoardBuilder::build`].
+    pub fn drop_invalid_en_passant(mut self) -> Self {
+        if let Some(pos) = self.en_passant {
+            if !self.is_valid_en_passant(pos) {
+                self.en_passant = None;
+            }
+        }
+        self
+    }
+
+    /// Whether the king and the rook of the castling side are on their home squares.
+    fn is_valid_castling(&self, color: PieceColor, side: CastlingSide) -> bool {
+        let rook_file = match side {
+            CastlingSide::Short => File::H,
+            CastlingSide::Long => File::A,
+        };
+        let king = Piece::new(color, PieceType::King);
+        let rook = Piece::new(color, PieceType::Rook);
+        self.at(color.king_home()) == Square::Piece(king)
+            && self.at(Pos::new(rook_file, color.king_rank())) == Square::Piece(rook)
+    }
+
+    pub fn build(self) -> Result<BoardState, PositionError> {
+        for color in [PieceColor::White, PieceColor::Black] {
+            if self.count(Piece::new(color, PieceType::King)) != 1 {
+                return Err(PositionError::KingCount(color));
+            }
+        }
+        for (index, square) in self.squares.iter().enumerate() {
+            let pos = BoardState::pos_by_square_index(index);
+            let is_back_rank = pos.rank == Rank::new(1) || pos.rank == Rank::new(8);
+            if let Square::Piece(Piece {
+                kind: PieceType::Pawn,
+                ..
+            }) = square
+            {
+                if is_back_rank {
+                    return Err(PositionError::PawnOnBackRank(pos));
+                }
+            }
+        }
+        for &(color, side) in &self.castling {
+            if !self.is_valid_castling(color, side) {
+                return Err(PositionError::InvalidCastling(color, side));
+            }
+        }
+        if let Some(pos) = self.en_passant {
+            if !self.is_valid_en_passant(pos) {
+                return Err(PositionError::InvalidEnPassant(pos));
+            }
+        }
+
+        let castling_state = |color| CastlingState {
+            king_moved: false,
+            rook_moved_short: !self.castling.contains(&(color, CastlingSide::Short)),
+            rook_moved_long: !self.castling.contains(&(color, CastlingSide::Long)),
+        };
+        let state = BoardState::from_setup(
+            self.squares.clone(),
+            self.turn,
+            castling_state(PieceColor::White),
+            castling_state(PieceColor::Black),
+            self.en_passant,
+            self.halfmove_clock,
+            self.move_number,
+        );
+        if state.is_king_attacked(!self.turn) {
+            return Err(PositionError::OpponentInCheck);
+        }
+        Ok(state)
+    }
+
+    fn at(&self, pos: Pos) -> Square {
+        self.squares[SquareIndex::from(pos).get()]
+    }
+
+    fn count(&self, piece: Piece) -> usize {
+        self.squares
+            .iter()
+            .filter(|s| **s == Square::Piece(piece))
+            .count()
+    }
+
+    /// The skipped square and the one the pawn came from must be empty, with the opponent's
+    /// pawn right behind the skipped square.
+    fn is_valid_en_passant(&self, pos: Pos) -> bool {
+        let (start_rank, rank, pawn_rank) = match self.turn {
+            PieceColor::White => (7, 6, 5),
+            PieceColor::Black => (2, 3, 4),
+        };
+        let pawn = Piece::new(!self.turn, PieceType::Pawn);
+        pos.rank == Rank::new(rank)
+            && self.at(pos) == Square::Empty
+            && self.at(Pos::new(pos.file, Rank::new(start_rank))) == Square::Empty
+            && self.at(Pos::new(pos.file, Rank::new(pawn_rank))) == Square::Piece(pawn)
+    }
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(s: &str) -> Pos {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn queen_mate() {
+        let state = BoardBuilder::new()
+            .piece(pos("g6"), Piece::white_king())
+            .piece(pos("g7"), Piece::white_queen())
+            .piece(pos("h8"), Piece::black_king())
+            .turn(PieceColor::Black)
+            .build()
+            .unwrap();
+        assert!(state.is_checkmate(PieceColor::Black));
+    }
+
+    #[test]
+    fn invalid_positions() {
+        let kings = BoardBuilder::new()
+            .piece(pos("e1"), Piece::white_king())
+            .piece(pos("e8"), Piece::black_king());
+        assert!(kings.clone().build().is_ok());
+        assert_eq!(
+            BoardBuilder::new().build().unwrap_err(),
+            PositionError::KingCount(PieceColor::White)
+        );
+        assert_eq!(
+            kings
+                .clone()
+                .piece(pos("a1"), Piece::white_pawn())
+                .build()
+                .unwrap_err(),
+            PositionError::PawnOnBackRank(pos("a1"))
+        );
+        assert_eq!(
+            kings
+                .clone()
+                .piece(pos("e4"), Piece::white_rook())
+                .build()
+                .unwrap_err(),
+            PositionError::OpponentInCheck
+        );
+        assert_eq!(
+            kings
+                .clone()
+                .castling(PieceColor::White, CastlingSide::Short)
+                .build()
+                .unwrap_err(),
+            PositionError::InvalidCastling(PieceColor::White, CastlingSide::Short)
+        );
+        assert_eq!(
+            kings.en_passant(pos("d6")).build().unwrap_err(),
+            PositionError::InvalidEnPassant(pos("d6"))
+        );
+    }
+}
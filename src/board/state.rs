@@ -1,4 +1,6 @@
+use crate::board::attacks::AttackMap;
 use crate::board::castling::CastlingState;
+use crate::board::BoardBuilder;
 use crate::board::Handles;
 use crate::board::Square;
 use crate::fen::parse_fen;
@@ -6,63 +8,414 @@ use crate::fen::STARTING_POSITION;
 use crate::moves::internal::Move;
 use crate::moves::san::Move as SanMove;
 use crate::moves::CastlingSide;
+use crate::moves::MoveEvent;
 use crate::moves::MoveNumber;
 use crate::moves::Ply;
 use crate::moves::PromotedTo;
 use crate::piece::{Piece, PieceColor, PieceType};
 use crate::pos::{
-    DiagonalDirection, File, HorizontalDirection, Pos, Rank, UnboundedPos, VerticalDirection,
+    DiagonalDirection, File, HorizontalDirection, Pos, Rank, SquareIndex, UnboundedPos,
+    VerticalDirection,
 };
 use crate::take_while::TakeWhileInclusiveExt;
+use std::fmt;
 
-pub type IsHighlighted = bool;
+/// How a legal move destination is marked on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightKind {
+    /// A move to an empty square.
+    Move,
+    Capture,
+}
+
+/// A square the selected piece can move to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MoveTarget {
+    pub to: Pos,
+    pub kind: HighlightKind,
+    pub gives_check: bool,
+}
 
 pub type Turn = PieceColor;
 
+/// Why [`BoardState::from_san_move`] found no move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanResolveError {
+    /// The side to move has no piece of that kind on the given file or rank.
+    NoMatchingPiece,
+    /// More than one piece can make the move, like `Nd2` with knights on b1 and f3.
+    Ambiguous,
+    IllegalMove,
+}
+
+impl fmt::Display for SanResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoMatchingPiece => write!(f, "no piece can make the move"),
+            Self::Ambiguous => write!(f, "more than one piece can make the move"),
+            Self::IllegalMove => write!(f, "illegal move"),
+        }
+    }
+}
+
+/// What a move changed, for [`BoardState::unmake_move`].
+#[derive(Debug, Clone)]
+struct Undo {
+    /// The squares changed by the move with their earlier contents.
+    squares: [Option<(Pos, Square)>; 4],
+    castling_white: CastlingState,
+    castling_black: CastlingState,
+    plies_since_last_non_repeatable_move: Ply,
+    move_number: MoveNumber,
+    en_passant: Option<Pos>,
+    last_irreversible_ply: usize,
+    attacks: AttackMap,
+    #[cfg(feature = "crazyhouse")]
+    hands: [Vec<PieceType>; 2],
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GameResult {
-    WinByCheckmate { checkmated_side: PieceColor },
+    WinByCheckmate {
+        checkmated_side: PieceColor,
+    },
     DrawByStalemate,
+    Resignation {
+        resigned_side: PieceColor,
+    },
+    /// `flagged_side` ran out of time.
+    Timeout {
+        flagged_side: PieceColor,
+    },
+    DrawByAgreement,
+    /// Claimed after fifty moves without a capture or a pawn move.
+    DrawByFiftyMoveRule,
+    /// Automatic after seventy-five moves without a capture or a pawn move.
+    DrawBySeventyFiveMoveRule,
+    /// Claimed when the same position occurs for the third time.
+    DrawByThreefoldRepetition,
+    /// Neither side has enough pieces left to checkmate.
+    DrawByInsufficientMaterial,
+    /// Antichess: `winner` has nothing but the king left.
+    #[cfg(feature = "antichess")]
+    WinByLosingAllPieces {
+        winner: PieceColor,
+    },
+}
+
+impl GameResult {
+    /// Result token used in PGN: `1-0`, `0-1` or `1/2-1/2`.
+    pub fn pgn_token(&self) -> &'static str {
+        use PieceColor::*;
+        match self {
+            Self::WinByCheckmate {
+                checkmated_side: loser,
+            }
+            | Self::Resignation {
+                resigned_side: loser,
+            }
+            | Self::Timeout {
+                flagged_side: loser,
+            } => match loser {
+                White => "0-1",
+                Black => "1-0",
+            },
+            Self::DrawByStalemate
+            | Self::DrawByAgreement
+            | Self::DrawByFiftyMoveRule
+            | Self::DrawBySeventyFiveMoveRule
+            | Self::DrawByThreefoldRepetition
+            | Self::DrawByInsufficientMaterial => "1/2-1/2",
+            #[cfg(feature = "antichess")]
+            Self::WinByLosingAllPieces { winner } => match winner {
+                White => "1-0",
+                Black => "0-1",
+            },
+        }
+    }
+
+    /// Human-readable outcome, like `White wins by checkmate`.
+    pub fn description(&self) -> String {
+        match *self {
+            Self::WinByCheckmate { checkmated_side } => {
+                format!("{} wins by checkmate", !checkmated_side)
+            }
+            Self::DrawByStalemate => "Draw by stalemate".to_string(),
+            Self::Resignation { resigned_side } => {
+                format!("{} wins by resignation", !resigned_side)
+            }
+            Self::Timeout { flagged_side } => format!("{} wins on time", !flagged_side),
+            Self::DrawByAgreement => "Draw by agreement".to_string(),
+            Self::DrawByFiftyMoveRule => "Draw by the fifty-move rule".to_string(),
+            Self::DrawBySeventyFiveMoveRule => "Draw by the seventy-five-move rule".to_string(),
+            Self::DrawByThreefoldRepetition => "Draw by threefold repetition".to_string(),
+            Self::DrawByInsufficientMaterial => "Draw by insufficient material".to_string(),
+            #[cfg(feature = "antichess")]
+            Self::WinByLosingAllPieces { winner } => {
+                format!("{winner} wins by losing all pieces")
+            }
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description())
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct BoardState {
-    pub squares: Vec<(Square, IsHighlighted)>,
+    pub squares: Vec<Square>,
+    highlights: Vec<MoveTarget>,
     pub selected_piece: Option<(Piece, Pos)>,
     pub turn: Turn,
     castling_white: CastlingState,
     castling_black: CastlingState,
     plies_since_last_non_repeatable_move: Ply,
     move_number: MoveNumber,
+    /// The square skipped by a pawn which just moved two squares.
+    en_passant: Option<Pos>,
+    attacks: AttackMap,
+    /// Hashes of the earlier positions of the game, with whether the side to move was in check.
+    history: Vec<(u64, bool)>,
+    /// Length of `history` right after the last pawn move, capture or castling. Earlier
+    /// positions can't occur again.
+    last_irreversible_ply: usize,
+    /// One entry per move made with [`BoardState::make_move`].
+    undo: Vec<Undo>,
     pub game_result: Option<GameResult>,
     pub handles: Handles,
+    /// Crazyhouse: captured pieces that White and Black can drop.
+    #[cfg(feature = "crazyhouse")]
+    hands: [Vec<PieceType>; 2],
+    /// Crazyhouse rules: captured pieces go to the hand and can be dropped.
+    #[cfg(feature = "crazyhouse")]
+    crazyhouse: bool,
+    /// Antichess rules: captures are mandatory and losing all pieces but the king wins.
+    #[cfg(feature = "antichess")]
+    antichess: bool,
 }
 
 impl BoardState {
     pub fn new() -> Self {
-        let squares = parse_fen(STARTING_POSITION)
-            .into_iter()
-            .map(|s| (s, false))
-            .collect();
-        Self {
-            squares,
+        let mut state = Self {
+            squares: parse_fen(STARTING_POSITION),
+            highlights: Vec::new(),
             selected_piece: None,
             turn: Turn::White,
             castling_white: CastlingState::new(),
             castling_black: CastlingState::new(),
             plies_since_last_non_repeatable_move: 0,
             move_number: 1,
+            en_passant: None,
+            attacks: AttackMap::new(),
+            history: Vec::new(),
+            last_irreversible_ply: 0,
+            undo: Vec::new(),
             game_result: None,
             handles: Handles::new(),
+            #[cfg(feature = "crazyhouse")]
+            hands: Default::default(),
+            #[cfg(feature = "crazyhouse")]
+            crazyhouse: false,
+            #[cfg(feature = "antichess")]
+            antichess: false,
+        };
+        state.attacks = state.compute_attacks();
+        state
+    }
+
+    /// Position used by [`BoardBuilder`](crate::board::BoardBuilder), which validates it.
+    pub(super) fn from_setup(
+        squares: Vec<Square>,
+        turn: Turn,
+        castling_white: CastlingState,
+        castling_black: CastlingState,
+        en_passant: Option<Pos>,
+        plies_since_last_non_repeatable_move: Ply,
+        move_number: MoveNumber,
+    ) -> Self {
+        let mut state = Self {
+            squares,
+            turn,
+            castling_white,
+            castling_black,
+            en_passant,
+            plies_since_last_non_repeatable_move,
+            move_number,
+            ..Self::new()
+        };
+        state.attacks = state.compute_attacks();
+        state
+    }
+
+    /// Identifies the position for repetition detection: the placement, the side to move,
+    /// castling rights and the en passant capture, if any.
+    pub fn position_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.squares.hash(&mut hasher);
+        self.turn.hash(&mut hasher);
+        for color in [PieceColor::White, PieceColor::Black] {
+            for side in [CastlingSide::Short, CastlingSide::Long] {
+                self.has_castling_right(color, side).hash(&mut hasher);
+            }
+        }
+        // The en passant square only matters if a pawn can actually capture there.
+        let en_passant = self.en_passant.filter(|pos| {
+            self.attackers_of(*pos, self.turn).iter().any(|p| {
+                matches!(
+                    self.square_by_pos(*p),
+                    Square::Piece(Piece {
+                        kind: PieceType::Pawn,
+                        ..
+                    })
+                )
+            })
+        });
+        en_passant.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The number of plies played before the last pawn move, capture or castling, counting
+    /// from the start of the game or the set up position.
+    pub fn last_irreversible_ply(&self) -> usize {
+        self.last_irreversible_ply
+    }
+
+    /// The earlier positions which may still repeat.
+    fn reversible_history(&self) -> &[(u64, bool)] {
+        &self.history[self.last_irreversible_ply..]
+    }
+
+    /// How many times the current position occurred in the game, including now.
+    pub fn repetition_count(&self) -> usize {
+        let hash = self.position_hash();
+        1 + self
+            .reversible_history()
+            .iter()
+            .filter(|(h, _)| *h == hash)
+            .count()
+    }
+
+    /// Whether the position repeats after one side gave check on every move since its
+    /// previous occurrence. Informational only, the draw itself is by repetition.
+    pub fn is_perpetual_check_likely(&self) -> bool {
+        let hash = self.position_hash();
+        let history = self.reversible_history();
+        let previous = match history.iter().rposition(|(h, _)| *h == hash) {
+            Some(index) => index,
+            None => return false,
+        };
+        let cycle: Vec<bool> = history[previous..]
+            .iter()
+            .map(|(_, in_check)| *in_check)
+            .chain([self.in_check()])
+            .collect();
+        // The same side is to move in every other position of the cycle, starting from
+        // the last one, the current position.
+        let checked = |parity| {
+            cycle
+                .iter()
+                .rev()
+                .skip(parity)
+                .step_by(2)
+                .all(|in_check| *in_check)
+        };
+        checked(0) || checked(1)
+    }
+
+    /// The same position with colors swapped and the board flipped top to bottom, so the
+    /// other side is to move.
+    pub fn mirror_vertical(&self) -> BoardState {
+        self.mirrored(
+            |pos| Pos::new(pos.file, Rank::new(9 - pos.rank.get())),
+            |piece| Piece {
+                color: !piece.color,
+                ..piece
+            },
+        )
+    }
+
+    /// The position with the board flipped left to right. Castling rights are lost, since
+    /// the kings end up on the d-file.
+    pub fn mirror_horizontal(&self) -> BoardState {
+        self.mirrored(
+            |pos| Pos::new(File::from_u8(9 - pos.file.as_u8()), pos.rank),
+            |piece| piece,
+        )
+    }
+
+    fn mirrored(&self, flip: impl Fn(Pos) -> Pos, recolor: impl Fn(Piece) -> Piece) -> BoardState {
+        let mut builder = BoardBuilder::new()
+            .halfmove_clock(self.plies_since_last_non_repeatable_move)
+            .move_number(self.move_number);
+        for (piece, pos) in self.pieces() {
+            builder = builder.piece(flip(pos), recolor(piece));
+        }
+        let king = |color| Piece {
+            kind: PieceType::King,
+            color,
+        };
+        let turn = recolor(king(self.turn)).color;
+        builder = builder.turn(turn);
+        for color in [PieceColor::White, PieceColor::Black] {
+            for side in [CastlingSide::Short, CastlingSide::Long] {
+                if self.has_castling_right(color, side) {
+                    builder = builder.castling(recolor(king(color)).color, side);
+                }
+            }
+        }
+        if let Some(pos) = self.en_passant {
+            builder = builder.en_passant(flip(pos));
+        }
+        builder
+            .drop_invalid_castling()
+            .drop_invalid_en_passant()
+            .build()
+            .expect("mirroring a valid position")
+    }
+
+    /// Plays `moves` from the starting position, failing with the index of the first
+    /// illegal one.
+    pub fn from_moves(moves: &[Move]) -> Result<Self, usize> {
+        let mut state = Self::new();
+        for (index, mv) in moves.iter().enumerate() {
+            if !state.all_legal_moves().contains(mv) {
+                return Err(index);
+            }
+            state.make_move(*mv);
         }
+        Ok(state)
+    }
+
+    /// The color whose turn it is.
+    pub fn side_to_move(&self) -> PieceColor {
+        self.turn
+    }
+
+    /// Same as [`BoardState::side_to_move`].
+    pub fn turn(&self) -> PieceColor {
+        self.turn
+    }
+
+    /// The color that just moved, or will move next.
+    pub fn opponent(&self) -> PieceColor {
+        !self.turn
     }
 
     fn switch_turn(&mut self) {
-        self.turn = !self.turn;
+        self.turn = self.opponent();
     }
 
     pub fn is_castling_possible(&self, side: CastlingSide) -> bool {
-        let castling = self.castling(self.turn);
+        self.has_castling_right(self.side_to_move(), side)
+    }
+
+    /// Whether neither the king nor the rook on the given side has moved yet.
+    pub fn has_castling_right(&self, color: PieceColor, side: CastlingSide) -> bool {
+        let castling = self.castling(color);
         if side == CastlingSide::Short {
             !castling.king_moved && !castling.rook_moved_short
         } else {
@@ -70,14 +423,73 @@ impl BoardState {
         }
     }
 
+    /// Whether the side to move can castle right now, not only has the right to.
+    pub fn can_castle_now(&self, side: CastlingSide) -> bool {
+        let king = Piece {
+            kind: PieceType::King,
+            color: self.side_to_move(),
+        };
+        self.is_castling_possible(side) && self.is_legal_move(king, Move::castling(side))
+    }
+
     pub fn plies_since_last_non_repeatable_move(&self) -> Ply {
         self.plies_since_last_non_repeatable_move
     }
 
+    /// Sets the plies played since the last capture or pawn move, for positions set up
+    /// without their moves. It's 0 in a new position.
+    pub fn set_halfmove_clock(&mut self, plies: Ply) {
+        self.plies_since_last_non_repeatable_move = plies;
+    }
+
+    /// Whether a draw can be claimed under the fifty-move rule.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.plies_since_last_non_repeatable_move >= 100
+    }
+
+    /// Whether the game is drawn automatically under the seventy-five-move rule.
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.plies_since_last_non_repeatable_move >= 150
+    }
+
     pub fn move_number(&self) -> MoveNumber {
         self.move_number
     }
 
+    pub fn en_passant(&self) -> Option<Pos> {
+        self.en_passant
+    }
+
+    pub fn is_capture(&self, mv: Move) -> bool {
+        match mv {
+            Move::Regular { to, .. } => {
+                self.is_square_occupied(to) || self.en_passant_victim(mv).is_some()
+            }
+            Move::Castling { .. } => false,
+            #[cfg(feature = "crazyhouse")]
+            Move::Drop { .. } => false,
+        }
+    }
+
+    /// Position of the pawn captured en passant by the move, if it is such a capture.
+    fn en_passant_victim(&self, mv: Move) -> Option<Pos> {
+        let (from, to) = match mv {
+            Move::Regular { from, to, .. } => (from, to),
+            Move::Castling { .. } => return None,
+            #[cfg(feature = "crazyhouse")]
+            Move::Drop { .. } => return None,
+        };
+        let is_pawn = matches!(
+            self.square_by_pos(from),
+            Square::Piece(Piece {
+                kind: PieceType::Pawn,
+                ..
+            })
+        );
+        let is_en_passant = is_pawn && from.file != to.file && self.en_passant == Some(to);
+        is_en_passant.then(|| Pos::new(to.file, from.rank))
+    }
+
     fn castling(&self, color: PieceColor) -> &CastlingState {
         if color == PieceColor::White {
             &self.castling_white
@@ -94,7 +506,170 @@ impl BoardState {
         }
     }
 
-    pub fn make_move(&mut self, mv: Move) {
+    pub fn make_move(&mut self, mv: Move) -> MoveEvent {
+        let is_capture = self.is_capture(mv);
+        self.push_move(mv);
+        if self.is_checkmate(self.side_to_move())
+            || self.is_stalemate()
+            || self.is_seventy_five_move_draw()
+        {
+            MoveEvent::GameEnd
+        } else if self.is_king_attacked(self.side_to_move()) {
+            MoveEvent::Check
+        } else if matches!(
+            mv,
+            Move::Regular {
+                promoted: Some(_),
+                ..
+            }
+        ) {
+            MoveEvent::Promotion
+        } else if is_capture {
+            MoveEvent::Capture
+        } else if matches!(mv, Move::Castling { .. }) {
+            MoveEvent::Castle
+        } else {
+            MoveEvent::Move
+        }
+    }
+
+    /// Makes the move like [`BoardState::make_move`] without working out the [`MoveEvent`],
+    /// for searches. The position is still recorded for repetitions, and the move can be
+    /// taken back with [`BoardState::unmake_move`].
+    pub(crate) fn push_move(&mut self, mv: Move) {
+        let is_capture = self.is_capture(mv);
+        let is_pawn_move = matches!(
+            mv.from().map(|from| self.square_by_pos(from)),
+            Some(Square::Piece(Piece {
+                kind: PieceType::Pawn,
+                ..
+            }))
+        );
+        let mut squares = [None; 4];
+        for (square, pos) in squares.iter_mut().zip(self.changed_squares(mv)) {
+            *square = Some((pos, self.square_by_pos(pos)));
+        }
+        self.undo.push(Undo {
+            squares,
+            castling_white: self.castling_white.clone(),
+            castling_black: self.castling_black.clone(),
+            plies_since_last_non_repeatable_move: self.plies_since_last_non_repeatable_move,
+            move_number: self.move_number,
+            en_passant: self.en_passant,
+            last_irreversible_ply: self.last_irreversible_ply,
+            attacks: self.attacks.clone(),
+            #[cfg(feature = "crazyhouse")]
+            hands: self.hands.clone(),
+        });
+        self.history.push((self.position_hash(), self.in_check()));
+        if is_capture || is_pawn_move || matches!(mv, Move::Castling { .. }) {
+            self.last_irreversible_ply = self.history.len();
+        }
+        self.apply_move(mv);
+    }
+
+    /// Takes back the last move made with [`BoardState::make_move`], returns `false` if
+    /// there is none.
+    pub fn unmake_move(&mut self) -> bool {
+        let undo = match self.undo.pop() {
+            Some(undo) => undo,
+            None => return false,
+        };
+        self.history.pop();
+        for (pos, square) in undo.squares.into_iter().flatten() {
+            *self.square_by_pos_mut(pos) = square;
+        }
+        self.castling_white = undo.castling_white;
+        self.castling_black = undo.castling_black;
+        self.plies_since_last_non_repeatable_move = undo.plies_since_last_non_repeatable_move;
+        self.move_number = undo.move_number;
+        self.en_passant = undo.en_passant;
+        self.last_irreversible_ply = undo.last_irreversible_ply;
+        self.attacks = undo.attacks;
+        #[cfg(feature = "crazyhouse")]
+        {
+            self.hands = undo.hands;
+        }
+        self.switch_turn();
+        self.selected_piece = None;
+        self.highlights.clear();
+        true
+    }
+
+    /// Makes the move and updates the attack maps of the pieces affected by it.
+    fn apply_move(&mut self, mv: Move) {
+        let changed = self.changed_squares(mv);
+        // Only the pieces standing on the changed squares and the sliders whose rays
+        // reach them can attack something else after the move.
+        let affected: Vec<_> = self
+            .pieces()
+            .filter(|(piece, pos)| {
+                changed.contains(pos)
+                    || (piece.kind.is_slider()
+                        && self
+                            .attacked_squares(*piece, *pos)
+                            .iter()
+                            .any(|p| changed.contains(p)))
+            })
+            .collect();
+        for (piece, pos) in &affected {
+            let squares = self.attacked_squares(*piece, *pos);
+            self.attacks.remove(piece.color, squares);
+        }
+
+        self.move_pieces(mv);
+
+        let sliders = affected
+            .into_iter()
+            .filter(|(_, pos)| !changed.contains(pos));
+        let on_changed = changed
+            .iter()
+            .filter_map(|pos| match self.square_by_pos(*pos) {
+                Square::Piece(piece) => Some((piece, *pos)),
+                Square::Empty => None,
+            });
+        let to_add: Vec<_> = sliders.chain(on_changed).collect();
+        for (piece, pos) in to_add {
+            let squares = self.attacked_squares(piece, pos);
+            self.attacks.add(piece.color, squares);
+        }
+    }
+
+    /// Squares which occupancy is changed by the move.
+    fn changed_squares(&self, mv: Move) -> Vec<Pos> {
+        match mv {
+            Move::Regular { from, to, .. } => {
+                let mut changed = vec![from, to];
+                changed.extend(self.en_passant_victim(mv));
+                changed
+            }
+            Move::Castling { side } => {
+                let rank = self.side_to_move().king_rank();
+                let (king_dest, rook_home, rook_dest) = match side {
+                    CastlingSide::Short => (File::G, File::H, File::F),
+                    CastlingSide::Long => (File::C, File::A, File::D),
+                };
+                vec![
+                    self.side_to_move().king_home(),
+                    Pos::new(king_dest, rank),
+                    Pos::new(rook_home, rank),
+                    Pos::new(rook_dest, rank),
+                ]
+            }
+            #[cfg(feature = "crazyhouse")]
+            Move::Drop { to, .. } => vec![to],
+        }
+    }
+
+    fn compute_attacks(&self) -> AttackMap {
+        let mut attacks = AttackMap::new();
+        for (piece, pos) in self.pieces() {
+            attacks.add(piece.color, self.attacked_squares(piece, pos));
+        }
+        attacks
+    }
+
+    fn move_pieces(&mut self, mv: Move) {
         match mv {
             Move::Regular { from, to, promoted } => {
                 let piece = if let Square::Piece(piece) = self.square_by_pos(from) {
@@ -105,33 +680,52 @@ impl BoardState {
                 if piece.kind == PieceType::King {
                     self.castling_mut(piece.color).king_moved = true;
                 }
-                if piece.kind == PieceType::Rook && from.file == File::A {
-                    self.castling_mut(piece.color).rook_moved_long = true;
+                // Only the rooks on their home squares matter, in a position set up from FEN
+                // other rooks may stand on the same files. A rook captured at home loses its
+                // right as well.
+                for (pos, color) in [(from, piece.color), (to, !piece.color)] {
+                    if pos == Pos::new(File::A, color.king_rank()) {
+                        self.castling_mut(color).rook_moved_long = true;
+                    }
+                    if pos == Pos::new(File::H, color.king_rank()) {
+                        self.castling_mut(color).rook_moved_short = true;
+                    }
+                }
+                let is_capture = self.is_capture(mv);
+                #[cfg(feature = "crazyhouse")]
+                if let Square::Piece(captured) =
+                    self.square_by_pos(self.en_passant_victim(mv).unwrap_or(to))
+                {
+                    if self.crazyhouse {
+                        self.hand_mut(piece.color).push(captured.kind);
+                    }
                 }
-                if piece.kind == PieceType::Rook && from.file == File::H {
-                    self.castling_mut(piece.color).rook_moved_short = true;
+                if let Some(victim) = self.en_passant_victim(mv) {
+                    *self.square_by_pos_mut(victim) = Square::Empty;
                 }
                 if let Some(promoted) = promoted {
-                    println!("Promoting: {piece:?}, {to:?}");
-                    self.make_move_promote(from, to, promoted);
+                    self.make_move_promote(from, to, promoted.to_piece(piece.color));
                 } else {
                     self.make_move_inner(from, to);
                 }
 
-                let is_capture = self.is_square_occupied(to);
                 let is_pawn_move = piece.kind == PieceType::Pawn;
                 if is_capture || is_pawn_move {
                     self.plies_since_last_non_repeatable_move = 0;
                 } else {
                     self.plies_since_last_non_repeatable_move += 1;
                 }
+                let is_double_push = is_pawn_move && from.rank.get().abs_diff(to.rank.get()) == 2;
+                let skipped = Rank::new((from.rank.get() + to.rank.get()) / 2);
+                self.en_passant = is_double_push.then_some(Pos::new(from.file, skipped));
             }
             Move::Castling { side } => {
                 use CastlingSide::*;
                 use PieceColor::*;
                 self.plies_since_last_non_repeatable_move += 1;
-                self.castling_mut(self.turn).king_moved = true;
-                let rank = if self.turn == White {
+                self.en_passant = None;
+                self.castling_mut(self.side_to_move()).king_moved = true;
+                let rank = if self.side_to_move() == White {
                     Rank::new(1)
                 } else {
                     Rank::new(8)
@@ -145,20 +739,33 @@ impl BoardState {
                 let rook_dest_long = Pos::new(File::D, rank);
                 match side {
                     Short => {
-                        self.castling_mut(self.turn).rook_moved_short = true;
+                        self.castling_mut(self.side_to_move()).rook_moved_short = true;
                         self.make_move_inner(king_home, king_dest_short);
                         self.make_move_inner(rook_home_short, rook_dest_short);
                     }
                     Long => {
-                        self.castling_mut(self.turn).rook_moved_long = true;
+                        self.castling_mut(self.side_to_move()).rook_moved_long = true;
                         self.make_move_inner(king_home, king_dest_long);
                         self.make_move_inner(rook_home_long, rook_dest_long);
                     }
                 }
             }
+            #[cfg(feature = "crazyhouse")]
+            Move::Drop { piece, to } => {
+                let color = self.side_to_move();
+                let hand = self.hand_mut(color);
+                let index = hand
+                    .iter()
+                    .position(|kind| *kind == piece)
+                    .unwrap_or_else(|| panic!("Invalid move (not in hand): {mv:?}"));
+                hand.remove(index);
+                *self.square_by_pos_mut(to) = Square::Piece(Piece { kind: piece, color });
+                self.plies_since_last_non_repeatable_move += 1;
+                self.en_passant = None;
+            }
         }
         self.switch_turn();
-        if self.turn == Turn::White {
+        if self.side_to_move() == PieceColor::White {
             self.move_number += 1;
         }
     }
@@ -169,133 +776,196 @@ impl BoardState {
         *self.square_by_pos_mut(to) = piece;
     }
 
-    fn make_move_promote(&mut self, from: Pos, to: Pos, promote: PromotedTo) {
+    /// Replaces the pawn on `from` with `promoted` on `to`, overwriting a captured piece.
+    /// The capture itself is accounted for by the caller beforehand.
+    fn make_move_promote(&mut self, from: Pos, to: Pos, promoted: Piece) {
         *self.square_by_pos_mut(from) = Square::Empty;
-        *self.square_by_pos_mut(to) = Square::Piece(promote.to_piece(self.turn));
+        *self.square_by_pos_mut(to) = Square::Piece(promoted);
     }
 
-    pub fn hightlight_legal_moves(&mut self, piece: Piece, pos: Pos) {
-        let legal_moves = self.legal_moves(piece, pos);
-        for mv in legal_moves {
-            match mv {
-                Move::Regular { to, .. } => {
-                    let index = Self::square_index_by_pos(to);
-                    self.squares[index].1 = true;
-                }
+    /// Builds a legal move of the side to move from a pair of squares picked by the user.
+    /// A king moved two squares from its home square castles, a pawn reaching the last rank
+    /// is promoted to a queen.
+    pub fn legal_move_between(&self, from: Pos, to: Pos) -> Option<Move> {
+        let piece = match self.square_by_pos(from) {
+            Square::Piece(piece) if piece.color == self.side_to_move() => piece,
+            _ => return None,
+        };
+        let mv = if piece.kind == PieceType::King {
+            let king_rank = piece.color.king_rank();
+            let king_home = piece.color.king_home();
+            if from == king_home
+                && to == Pos::new(File::G, king_rank)
+                && self.is_castling_possible(CastlingSide::Short)
+            {
+                Move::castling(CastlingSide::Short)
+            } else if from == king_home
+                && to == Pos::new(File::C, king_rank)
+                && self.is_castling_possible(CastlingSide::Long)
+            {
+                Move::castling(CastlingSide::Long)
+            } else {
+                Move::new(from, to)
+            }
+        } else if piece.kind == PieceType::Pawn && (to.rank == (!piece.color).king_rank()) {
+            Move::new_with_promoted(from, to, Some(PromotedTo::Queen))
+        } else {
+            Move::new(from, to)
+        };
+        let is_legal = self.available_moves_iter(piece, from).any(|m| m == mv)
+            && self.is_legal_move(piece, mv);
+        is_legal.then_some(mv)
+    }
+
+    /// Parses a move of the side to move in UCI notation, e.g. `e2e4`, `e7e8n` or `e1g1`.
+    pub fn from_uci(&self, uci: &str) -> Option<Move> {
+        let from = uci.get(0..2)?.parse().ok()?;
+        let to = uci.get(2..4)?.parse().ok()?;
+        let mv = self.legal_move_between(from, to)?;
+        let promoted = match uci.get(4..)? {
+            "" => return Some(mv),
+            "q" => PromotedTo::Queen,
+            "r" => PromotedTo::Rook,
+            "b" => PromotedTo::Bishop,
+            "n" => PromotedTo::Knight,
+            _ => return None,
+        };
+        mv.promoted().map(|_| mv.with_promotion(promoted))
+    }
+
+    /// Depends on the side to move
+    pub fn to_uci(&self, mv: Move) -> String {
+        match mv {
+            Move::Regular { from, to, promoted } => {
+                let promoted = match promoted {
+                    Some(PromotedTo::Queen) => "q",
+                    Some(PromotedTo::Rook) => "r",
+                    Some(PromotedTo::Bishop) => "b",
+                    Some(PromotedTo::Knight) => "n",
+                    None => "",
+                };
+                format!("{from}{to}{promoted}")
+            }
+            Move::Castling { side } => {
+                let file = match side {
+                    CastlingSide::Short => File::G,
+                    CastlingSide::Long => File::C,
+                };
+                let to = Pos::new(file, self.side_to_move().king_rank());
+                format!("{from}{to}", from = self.side_to_move().king_home())
+            }
+            #[cfg(feature = "crazyhouse")]
+            Move::Drop { .. } => mv.to_string(),
+        }
+    }
+
+    /// Destinations of the legal moves of the piece, marked as captures or quiet moves.
+    pub fn move_targets(&self, piece: Piece, pos: Pos) -> Vec<MoveTarget> {
+        let mut targets: Vec<MoveTarget> = Vec::new();
+        for mv in self.legal_moves(piece, pos) {
+            let (to, kind) = match mv {
+                Move::Regular { to, .. } if self.is_capture(mv) => (to, HighlightKind::Capture),
+                Move::Regular { to, .. } => (to, HighlightKind::Move),
                 Move::Castling { side } => {
-                    let pos = match (side, piece.color) {
-                        (CastlingSide::Short, PieceColor::White) => Pos::new(File::G, Rank::new(1)),
-                        (CastlingSide::Short, PieceColor::Black) => Pos::new(File::G, Rank::new(8)),
-                        (CastlingSide::Long, PieceColor::White) => Pos::new(File::C, Rank::new(1)),
-                        (CastlingSide::Long, PieceColor::Black) => Pos::new(File::C, Rank::new(8)),
+                    let file = match side {
+                        CastlingSide::Short => File::G,
+                        CastlingSide::Long => File::C,
                     };
-                    let index = Self::square_index_by_pos(pos);
-                    self.squares[index].1 = true;
+                    let to = Pos::new(file, piece.color.king_rank());
+                    (to, HighlightKind::Move)
                 }
+                #[cfg(feature = "crazyhouse")]
+                Move::Drop { to, .. } => (to, HighlightKind::Move),
+            };
+            let gives_check = self.move_gives_check(mv);
+            // Promotions produce several moves to the same square.
+            match targets.iter_mut().find(|t| t.to == to) {
+                Some(target) => target.gives_check |= gives_check,
+                None => targets.push(MoveTarget {
+                    to,
+                    kind,
+                    gives_check,
+                }),
             }
         }
+        targets
+    }
+
+    pub fn hightlight_legal_moves(&mut self, piece: Piece, pos: Pos) {
+        self.highlights = self.move_targets(piece, pos);
     }
 
     pub fn stop_highlighting(&mut self) {
-        for (_, is_highlighted) in self.squares.iter_mut() {
-            *is_highlighted = false;
-        }
+        self.highlights.clear();
     }
 
-    /// Depends on self.turn
-    pub fn from_san_move(&self, mv: SanMove) -> Move {
-        match mv {
-            SanMove::Castling { side } => Move::Castling { side },
-            SanMove::Piece {
-                from, to, piece, ..
-            } => {
-                use crate::moves::san::FromPos;
-                let find_possible_solution = |file, rank| -> Option<Pos> {
-                    let restrict: Box<dyn Fn(&(Piece, Pos)) -> bool> = match (file, rank) {
-                        (Some(file), None) => Box::new(move |(_, pos)| pos.file == file),
-                        (None, Some(rank)) => Box::new(move |(_, pos)| pos.rank == rank),
-                        (None, None) => Box::new(|(_, _)| true),
-                        _ => unreachable!(),
-                    };
-                    self.pieces()
-                        .filter(|(Piece { kind, color }, pos)| {
-                            *kind == piece && *color == self.turn
-                        })
-                        .filter(restrict)
-                        .map(|(piece, pos)| {
-                            self.legal_moves(piece, pos)
-                                .iter()
-                                .map(|m| (*m, pos))
-                                .collect::<Vec<_>>()
-                        })
-                        .flatten()
-                        .filter(|(m, pos)| m.to() == Some(to))
-                        .map(|(_, pos)| pos)
-                        .next()
+    /// Finds the legal move of the side to move written as `mv`.
+    pub fn from_san_move(&self, mv: SanMove) -> Result<Move, SanResolveError> {
+        use crate::moves::san::FromPos;
+        let color = self.side_to_move();
+        let (kind, to, promoted) = match mv {
+            SanMove::Castling { side } => {
+                let mv = Move::Castling { side };
+                return match self.all_legal_moves().contains(&mv) {
+                    true => Ok(mv),
+                    false => Err(SanResolveError::IllegalMove),
                 };
-                let from = match from {
-                    Some(FromPos::Square(pos)) => pos,
-                    Some(FromPos::File(file)) => {
-                        find_possible_solution(Some(file), None).expect("Couldn't find move")
-                    }
-                    Some(FromPos::Rank(rank)) => {
-                        find_possible_solution(None, Some(rank)).expect("Couldn't find move")
-                    }
-                    None => find_possible_solution(None, None).expect("Couldn't find move"),
+            }
+            #[cfg(feature = "crazyhouse")]
+            SanMove::Drop { piece, to } => {
+                let mv = Move::Drop { piece, to };
+                return match self.drop_moves().contains(&mv) {
+                    true => Ok(mv),
+                    false => Err(SanResolveError::IllegalMove),
                 };
-                Move::new(from, to)
             }
-            SanMove::PawnPush { to, promoted } => {
-                let from = self
-                    .pieces()
-                    .filter(|(Piece { kind, color }, pos)| {
-                        *kind == PieceType::Pawn && *color == self.turn
-                    })
-                    .filter(|(piece, pos)| {
-                        self.legal_moves(*piece, *pos)
-                            .iter()
-                            .any(|m| m.to() == Some(to))
-                    })
-                    .map(|(_, pos)| pos)
-                    .next()
-                    .expect("Can't find pawn move");
-                Move::new_with_promoted(from, to, promoted)
+            SanMove::Piece { piece, to, .. } => (piece, to, None),
+            SanMove::PawnPush { to, promoted } | SanMove::PawnCapture { to, promoted, .. } => {
+                (PieceType::Pawn, to, promoted)
             }
+        };
+        let from_hint = |pos: &Pos| match mv {
+            SanMove::Piece { from, .. } => match from {
+                Some(FromPos::Square(from)) => *pos == from,
+                Some(FromPos::File(file)) => pos.file == file,
+                Some(FromPos::Rank(rank)) => pos.rank == rank,
+                None => true,
+            },
             SanMove::PawnCapture {
                 from_file,
                 from_rank,
-                to,
-                promoted,
-            } => {
-                let from = from_rank
-                    .map(|r| Pos::new(from_file, r))
-                    .unwrap_or_else(|| {
-                        self.pieces()
-                            .filter(|(Piece { kind, color }, pos)| {
-                                *kind == PieceType::Pawn
-                                    && *color == self.turn
-                                    && pos.file == from_file
-                            })
-                            .filter(|(piece, pos)| {
-                                self.legal_moves(*piece, *pos)
-                                    .iter()
-                                    .any(|m| m.to() == Some(to))
-                            })
-                            .map(|(_, pos)| pos)
-                            .next()
-                            .expect("Can't find pawn capture")
-                    });
-                Move::new_with_promoted(from, to, promoted)
-            }
-        }
-    }
-
-    /// Depends on self.turn
+                ..
+            } => pos.file == from_file && from_rank.is_none_or(|rank| pos.rank == rank),
+            _ => pos.file == to.file,
+        };
+        let piece = Piece::new(color, kind);
+        let mut origins = self.pieces_of(kind, color).filter(from_hint).peekable();
+        if origins.peek().is_none() {
+            return Err(SanResolveError::NoMatchingPiece);
+        }
+        let candidates: Vec<Move> = origins
+            .map(|from| Move::new_with_promoted(from, to, promoted))
+            .filter(|mv| {
+                self.legal_moves_iter(piece, mv.from().unwrap())
+                    .any(|m| m == *mv)
+            })
+            .collect();
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(SanResolveError::IllegalMove),
+            _ => Err(SanResolveError::Ambiguous),
+        }
+    }
+
+    /// The SAN of `mv`, which must be a move of the current position. The state isn't
+    /// changed.
     pub fn to_san_move(&self, mv: Move) -> SanMove {
         use PieceType::*;
         let (from, to, promoted) = match mv {
             Move::Regular { from, to, promoted } => (from, to, promoted),
             Move::Castling { side } => return SanMove::Castling { side },
+            #[cfg(feature = "crazyhouse")]
+            Move::Drop { piece, to } => return SanMove::Drop { piece, to },
         };
         match self.square_by_pos(from) {
             Square::Empty => panic!("Invalid move (no piece): {mv:?}. Can't produce SAN move"),
@@ -306,21 +976,15 @@ impl BoardState {
                 },
             ) => {
                 use crate::moves::san::FromPos;
-                let is_capture = self.is_square_occupied(to);
-                let count_same_pieces = self
-                    .pieces()
-                    .filter(|(Piece { kind, color }, _)| {
-                        *kind == piece.kind && *color == piece.color
-                    })
-                    .count();
+                let is_capture = self.is_capture(mv);
+                let count_same_pieces = self.pieces_of(piece.kind, piece.color).count();
                 let from = if count_same_pieces == 1 {
                     None
                 } else {
-                    let other_pieces = self.pieces().filter(|(Piece { kind, color }, pos)| {
-                        *pos != from && *kind == piece.kind && *color == piece.color
-                    });
-                    let legal_moves =
-                        other_pieces.map(|(piece, pos)| (pos, self.legal_moves(piece, pos)));
+                    let other_pieces = self
+                        .pieces_of(piece.kind, piece.color)
+                        .filter(|pos| *pos != from);
+                    let legal_moves = other_pieces.map(|pos| (pos, self.legal_moves(piece, pos)));
                     let matching_moves: Vec<_> = legal_moves
                         .map(|(pos, moves)| {
                             moves
@@ -333,9 +997,9 @@ impl BoardState {
                         .collect();
                     if matching_moves.is_empty() {
                         None
-                    } else if matching_moves.iter().any(|pos| pos.file != from.file) {
+                    } else if matching_moves.iter().all(|pos| pos.file != from.file) {
                         Some(FromPos::File(from.file))
-                    } else if matching_moves.iter().all(|pos| pos.file == from.file) {
+                    } else if matching_moves.iter().all(|pos| pos.rank != from.rank) {
                         Some(FromPos::Rank(from.rank))
                     } else {
                         Some(FromPos::Square(from))
@@ -349,14 +1013,12 @@ impl BoardState {
                 }
             }
             Square::Piece(pawn) => {
-                let is_capture = self.is_square_occupied(to);
+                let is_capture = self.is_capture(mv);
                 if is_capture {
                     let from_file = from.file;
                     let pawns_on_this_file = self
-                        .pieces()
-                        .filter(|(Piece { kind, color }, pos)| {
-                            *kind == Pawn && *color == pawn.color && pos.file == from_file
-                        })
+                        .pieces_of(Pawn, pawn.color)
+                        .filter(|pos| pos.file == from_file)
                         .count();
                     let from_rank = if pawns_on_this_file > 1 {
                         Some(from.rank)
@@ -376,287 +1038,596 @@ impl BoardState {
         }
     }
 
+    /// The SAN of a legal move with a `+` or `#` suffix when it gives check or mate, like
+    /// `Nf3`, `O-O` or `Qxf7#`.
+    pub fn san(&self, mv: Move) -> String {
+        let mut next = self.clone();
+        next.apply_move(mv);
+        let suffix = if next.is_checkmate(next.side_to_move()) {
+            "#"
+        } else if next.in_check() {
+            "+"
+        } else {
+            ""
+        };
+        format!("{}{suffix}", self.to_san_move(mv))
+    }
+
     pub fn available_moves(&self, piece: Piece, pos: Pos) -> Vec<Move> {
+        self.available_moves_iter(piece, pos).collect()
+    }
+
+    /// Same moves as [`BoardState::available_moves`], generated one at a time without
+    /// collecting them first.
+    pub fn available_moves_iter(&self, piece: Piece, pos: Pos) -> impl Iterator<Item = Move> + '_ {
+        use PieceType::*;
+        let origin = UnboundedPos::from_pos(pos);
+        let steps = match piece.kind {
+            Knight => Some(knight_jumps(origin)),
+            King => Some(king_steps(origin)),
+            _ => None,
+        };
+        let straight = matches!(piece.kind, Rook | Queen).then(|| self.straight_rays(pos));
+        let diagonal = matches!(piece.kind, Bishop | Queen).then(|| self.diagonal_rays(pos));
+        let moves = steps
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.to_pos())
+            .chain(straight.into_iter().flatten())
+            .chain(diagonal.into_iter().flatten())
+            .filter(move |p| !self.is_square_occupied_by_color(*p, piece.color))
+            .map(move |to| Move::new(pos, to));
+        let pawn_moves = (piece.kind == Pawn).then(|| self.pawn_moves_iter(piece, pos));
+        let castling = self.castling(piece.color);
+        let short_castling = (piece.kind == King && castling.is_short_possible())
+            .then_some(Move::castling(CastlingSide::Short));
+        let long_castling = (piece.kind == King && castling.is_long_possible())
+            .then_some(Move::castling(CastlingSide::Long));
+        pawn_moves
+            .into_iter()
+            .flatten()
+            .chain(moves)
+            .chain(short_castling)
+            .chain(long_castling)
+    }
+
+    /// Pushes and captures of a pawn, with a move for each piece it can promote to.
+    fn pawn_moves_iter(&self, piece: Piece, pos: Pos) -> impl Iterator<Item = Move> + '_ {
+        let is_white = piece.color == PieceColor::White;
+        let direction = if is_white {
+            VerticalDirection::Up
+        } else {
+            VerticalDirection::Down
+        };
+        // pawns can move up/down one square if not blocked
+        // they also can move up/down two squares if it's their first move
+        let is_first_move = if is_white {
+            pos.rank.get() == 2
+        } else {
+            pos.rank.get() == 7
+        };
+        let pushes = UnboundedPos::vertical(pos, if is_first_move { 2 } else { 1 }, direction)
+            .filter_map(|p| p.to_pos())
+            .take_while(|p| !self.is_square_occupied(*p));
+        // pawns can take other pieces in diagonals
+        let diagonal_directions = if is_white {
+            [DiagonalDirection::UpLeft, DiagonalDirection::UpRight]
+        } else {
+            [DiagonalDirection::DownLeft, DiagonalDirection::DownRight]
+        };
+        let captures = diagonal_directions.into_iter().flat_map(move |dir| {
+            UnboundedPos::diagonal(pos, 1, dir)
+                .filter_map(|p| p.to_pos())
+                .filter(move |p| {
+                    self.is_square_occupied_by_color(*p, !piece.color)
+                        || (piece.color == self.side_to_move() && self.en_passant == Some(*p))
+                })
+        });
+        pushes.chain(captures).flat_map(move |to| {
+            let promotions: &[Option<PromotedTo>] = if to.rank == (!piece.color).king_rank() {
+                &PROMOTIONS
+            } else {
+                &[None]
+            };
+            promotions
+                .iter()
+                .map(move |promoted| Move::new_with_promoted(pos, to, *promoted))
+        })
+    }
+
+    /// Squares attacked by the piece, including the ones occupied by pieces of the same color.
+    fn attacked_squares(&self, piece: Piece, pos: Pos) -> Vec<Pos> {
         use PieceType::*;
-        let mut result = Vec::new();
-        match piece.kind {
+        let origin = UnboundedPos::from_pos(pos);
+        let squares = match piece.kind {
             Pawn => {
-                let is_white = piece.color == PieceColor::White;
-                let direction = if is_white {
-                    VerticalDirection::Up
-                } else {
-                    VerticalDirection::Down
-                };
-                // pawns can move up/down one square if not blocked
-                // they also can move up/down two squares if it's their first move
-                let is_first_move = if is_white {
-                    pos.rank.get() == 2
-                } else {
-                    pos.rank.get() == 7
-                };
-                let moves =
-                    UnboundedPos::vertical(pos, if is_first_move { 2 } else { 1 }, direction)
-                        .into_iter()
-                        .filter_map(|p| p.to_pos())
-                        .take_while(|p| !self.is_square_occupied(*p));
-                // pawns can take other pieces in diagonals
-                let diagonal_directions = if is_white {
+                let directions = if piece.color == PieceColor::White {
                     [DiagonalDirection::UpLeft, DiagonalDirection::UpRight]
                 } else {
                     [DiagonalDirection::DownLeft, DiagonalDirection::DownRight]
                 };
-                let moves = moves.chain(
-                    diagonal_directions
-                        .into_iter()
-                        .map(|dir| {
-                            UnboundedPos::diagonal(pos, 1, dir)
-                                .filter_map(|p| p.to_pos())
-                                .filter(|p| self.is_square_occupied_by_color(*p, !piece.color))
-                        })
-                        .flatten(),
-                );
-                let moves = moves
-                    .map(|to| {
-                        if to.rank == (!piece.color).king_rank() {
-                            vec![
-                                Move::new_with_promoted(pos, to, Some(PromotedTo::Queen)),
-                                Move::new_with_promoted(pos, to, Some(PromotedTo::Bishop)),
-                                Move::new_with_promoted(pos, to, Some(PromotedTo::Rook)),
-                                Move::new_with_promoted(pos, to, Some(PromotedTo::Knight)),
-                            ]
-                        } else {
-                            vec![Move::new(pos, to)]
-                        }
-                    })
-                    .flatten();
-                result.extend(moves);
-            }
-            King => {
-                let castling = self.castling(piece.color);
-                let short_castling = castling
-                    .is_short_possible()
-                    .then(|| Move::castling(CastlingSide::Short));
-                let long_castling = castling
-                    .is_long_possible()
-                    .then(|| Move::castling(CastlingSide::Long));
-
-                let moves = UnboundedPos::vertical(pos, 1, VerticalDirection::Up)
-                    .into_iter()
-                    .chain(UnboundedPos::vertical(pos, 1, VerticalDirection::Down))
-                    .chain(UnboundedPos::horizontal(pos, 1, HorizontalDirection::Left))
-                    .chain(UnboundedPos::horizontal(pos, 1, HorizontalDirection::Right))
-                    .chain(UnboundedPos::diagonal(pos, 1, DiagonalDirection::UpLeft))
-                    .chain(UnboundedPos::diagonal(pos, 1, DiagonalDirection::UpRight))
-                    .chain(UnboundedPos::diagonal(pos, 1, DiagonalDirection::DownLeft))
-                    .chain(UnboundedPos::diagonal(pos, 1, DiagonalDirection::DownRight))
-                    .filter_map(|p| p.to_pos())
-                    .filter(|p| !self.is_square_occupied_by_color(*p, piece.color))
-                    .map(|to| Move::new(pos, to))
-                    .chain(short_castling)
-                    .chain(long_castling);
-                result.extend(moves);
-            }
-            Bishop => {
-                let directions = [
-                    DiagonalDirection::UpLeft,
-                    DiagonalDirection::UpRight,
-                    DiagonalDirection::DownLeft,
-                    DiagonalDirection::DownRight,
-                ];
-                let moves = directions
-                    .into_iter()
-                    .map(|dir| {
-                        UnboundedPos::diagonal(pos, 8, dir)
-                            .filter_map(|p| p.to_pos())
-                            .take_while_inclusive(|p| !self.is_square_occupied(*p))
-                            .filter(|p| !self.is_square_occupied_by_color(*p, piece.color))
-                    })
-                    .flatten()
-                    .map(|to| Move::new(pos, to));
-                result.extend(moves);
-            }
-            Knight => {
-                let moves = [
-                    UnboundedPos::from_pos(pos).up(2).left(1),
-                    UnboundedPos::from_pos(pos).up(2).right(1),
-                    UnboundedPos::from_pos(pos).down(2).left(1),
-                    UnboundedPos::from_pos(pos).down(2).right(1),
-                    UnboundedPos::from_pos(pos).up(1).left(2),
-                    UnboundedPos::from_pos(pos).up(1).right(2),
-                    UnboundedPos::from_pos(pos).down(1).left(2),
-                    UnboundedPos::from_pos(pos).down(1).right(2),
-                ]
-                .into_iter()
-                .filter_map(|p| p.to_pos())
-                .filter(|p| !self.is_square_occupied_by_color(*p, piece.color))
-                .map(|to| Move::new(pos, to));
-                result.extend(moves);
-            }
-            Rook => {
-                let directions = [VerticalDirection::Up, VerticalDirection::Down];
-                let moves = directions
+                directions
                     .into_iter()
-                    .map(|dir| {
-                        UnboundedPos::vertical(pos, 8, dir)
-                            .filter_map(|p| p.to_pos())
-                            .take_while_inclusive(|p| !self.is_square_occupied(*p))
-                            .filter(|p| !self.is_square_occupied_by_color(*p, piece.color))
-                    })
-                    .flatten()
-                    .chain(
-                        [HorizontalDirection::Left, HorizontalDirection::Right]
-                            .into_iter()
-                            .map(|dir| {
-                                UnboundedPos::horizontal(pos, 8, dir)
-                                    .filter_map(|p| p.to_pos())
-                                    .take_while_inclusive(|p| !self.is_square_occupied(*p))
-                                    .filter(|p| !self.is_square_occupied_by_color(*p, piece.color))
-                            })
-                            .flatten(),
-                    )
-                    .map(|to| Move::new(pos, to));
-                result.extend(moves);
+                    .flat_map(|dir| UnboundedPos::diagonal(pos, 1, dir))
+                    .collect()
             }
+            Knight => knight_jumps(origin).to_vec(),
+            King => king_steps(origin).to_vec(),
+            Bishop => return self.diagonal_rays(pos).collect(),
+            Rook => return self.straight_rays(pos).collect(),
             Queen => {
-                let directions = [VerticalDirection::Up, VerticalDirection::Down];
-                let moves = directions
-                    .into_iter()
-                    .map(|dir| {
-                        UnboundedPos::vertical(pos, 8, dir)
-                            .filter_map(|p| p.to_pos())
-                            .take_while_inclusive(|p| !self.is_square_occupied(*p))
-                            .filter(|p| !self.is_square_occupied_by_color(*p, piece.color))
-                    })
-                    .flatten()
-                    .chain(
-                        [HorizontalDirection::Left, HorizontalDirection::Right]
-                            .into_iter()
-                            .map(|dir| {
-                                UnboundedPos::horizontal(pos, 8, dir)
-                                    .filter_map(|p| p.to_pos())
-                                    .take_while_inclusive(|p| !self.is_square_occupied(*p))
-                                    .filter(|p| !self.is_square_occupied_by_color(*p, piece.color))
-                            })
-                            .flatten(),
-                    )
-                    .chain(
-                        [
-                            DiagonalDirection::UpLeft,
-                            DiagonalDirection::UpRight,
-                            DiagonalDirection::DownLeft,
-                            DiagonalDirection::DownRight,
-                        ]
-                        .into_iter()
-                        .map(|dir| {
-                            UnboundedPos::diagonal(pos, 8, dir)
-                                .filter_map(|p| p.to_pos())
-                                .take_while_inclusive(|p| !self.is_square_occupied(*p))
-                                .filter(|p| !self.is_square_occupied_by_color(*p, piece.color))
-                        })
-                        .flatten(),
-                    )
-                    .map(|to| Move::new(pos, to));
-                result.extend(moves);
+                return self
+                    .diagonal_rays(pos)
+                    .chain(self.straight_rays(pos))
+                    .collect()
             }
-        }
-        result
+        };
+        squares.into_iter().filter_map(|p| p.to_pos()).collect()
+    }
+
+    fn diagonal_rays(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        [
+            DiagonalDirection::UpLeft,
+            DiagonalDirection::UpRight,
+            DiagonalDirection::DownLeft,
+            DiagonalDirection::DownRight,
+        ]
+        .into_iter()
+        .flat_map(move |dir| {
+            UnboundedPos::diagonal(pos, 8, dir)
+                .filter_map(|p| p.to_pos())
+                .take_while_inclusive(|p| !self.is_square_occupied(*p))
+        })
+    }
+
+    fn straight_rays(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        let vertical = [VerticalDirection::Up, VerticalDirection::Down]
+            .into_iter()
+            .flat_map(move |dir| {
+                UnboundedPos::vertical(pos, 8, dir)
+                    .filter_map(|p| p.to_pos())
+                    .take_while_inclusive(|p| !self.is_square_occupied(*p))
+            });
+        let horizontal = [HorizontalDirection::Left, HorizontalDirection::Right]
+            .into_iter()
+            .flat_map(move |dir| {
+                UnboundedPos::horizontal(pos, 8, dir)
+                    .filter_map(|p| p.to_pos())
+                    .take_while_inclusive(|p| !self.is_square_occupied(*p))
+            });
+        vertical.chain(horizontal)
     }
 
     pub fn is_legal_move(&self, piece: Piece, mv: Move) -> bool {
         match mv {
+            // Out of check, only moving a pinned piece, the king or an en passant capture
+            // that empties two squares of a line can expose the king.
+            Move::Regular { from, .. }
+                if piece.kind != PieceType::King
+                    && self.en_passant_victim(mv).is_none()
+                    && !self.in_check()
+                    && !self.is_pinned(from) =>
+            {
+                true
+            }
             Move::Regular { .. } => !self.is_check(mv),
+            #[cfg(feature = "crazyhouse")]
+            Move::Drop { .. } => !self.is_check(mv),
             Move::Castling { side } => {
                 let rank = piece.color.king_rank();
-                let (king_path, rook_path) = match side {
-                    CastlingSide::Short => (
-                        vec![Pos::new(File::F, rank), Pos::new(File::G, rank)],
-                        vec![Pos::new(File::G, rank), Pos::new(File::F, rank)],
-                    ),
-                    CastlingSide::Long => (
-                        vec![Pos::new(File::D, rank), Pos::new(File::C, rank)],
-                        vec![
-                            Pos::new(File::B, rank),
-                            Pos::new(File::C, rank),
-                            Pos::new(File::D, rank),
-                        ],
-                    ),
+                let king_home = piece.color.king_home();
+                let (king_dest, rook_home) = match side {
+                    CastlingSide::Short => (File::G, File::H),
+                    CastlingSide::Long => (File::C, File::A),
                 };
+                let mut king_path = Pos::between(king_home, Pos::new(king_dest, rank));
+                king_path.push(Pos::new(king_dest, rank));
+                let rook_path = Pos::between(king_home, Pos::new(rook_home, rank));
                 let is_king_path_blocked =
                     king_path.iter().any(|pos| self.is_square_occupied(*pos));
                 let is_rook_path_blocked =
                     rook_path.iter().any(|pos| self.is_square_occupied(*pos));
-                let is_king_path_attacked = king_path
-                    .iter()
+                // Castling rights alone don't guarantee the rook is there in an edited position.
+                let rook = Piece {
+                    kind: PieceType::Rook,
+                    color: piece.color,
+                };
+                let is_rook_home =
+                    self.square_by_pos(Pos::new(rook_home, rank)) == Square::Piece(rook);
+                // The king can't castle out of check either.
+                let is_king_path_attacked = std::iter::once(&piece.color.king_home())
+                    .chain(&king_path)
                     .any(|pos| self.is_attacked(*pos, !piece.color));
 
-                !is_king_path_attacked && !is_king_path_blocked && !is_rook_path_blocked
+                is_rook_home
+                    && !is_king_path_attacked
+                    && !is_king_path_blocked
+                    && !is_rook_path_blocked
             }
         }
     }
 
     pub fn legal_moves(&self, piece: Piece, pos: Pos) -> Vec<Move> {
-        self.available_moves(piece, pos)
-            .into_iter()
-            .filter(|mv| self.is_legal_move(piece, *mv))
-            .collect()
+        self.legal_moves_iter(piece, pos).collect()
     }
 
-    pub fn is_checkmate(&self, checkmated_side: PieceColor) -> bool {
-        let total_legal_moves = self
+    /// Legal moves of the piece on `pos`, none if the square is empty or the piece isn't
+    /// of the side to move. Unlike [`BoardState::legal_moves`] it can't be given a wrong
+    /// piece.
+    pub fn moves_for_square(&self, pos: Pos) -> Vec<Move> {
+        match self.square_by_pos(pos) {
+            Square::Piece(piece) if piece.color == self.turn => self.legal_moves(piece, pos),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Same moves as [`BoardState::legal_moves`], checked for legality one at a time, so
+    /// stopping at the first one skips the rest of the checks.
+    pub fn legal_moves_iter(&self, piece: Piece, pos: Pos) -> impl Iterator<Item = Move> + '_ {
+        let moves = self
+            .available_moves_iter(piece, pos)
+            .filter(move |mv| self.is_legal_move(piece, *mv));
+        #[cfg(feature = "antichess")]
+        let must_capture = self.antichess && self.has_legal_capture();
+        #[cfg(feature = "antichess")]
+        let moves = moves.filter(move |mv| !must_capture || self.is_capture(*mv));
+        moves
+    }
+
+    /// Antichess: switches the captures-are-mandatory rules on or off. Kings stay royal,
+    /// so checks still have to be answered.
+    #[cfg(feature = "antichess")]
+    pub fn set_antichess(&mut self, enabled: bool) {
+        self.antichess = enabled;
+    }
+
+    /// Antichess: whether the side to move can capture, in which case it has to.
+    #[cfg(feature = "antichess")]
+    pub fn has_legal_capture(&self) -> bool {
+        self.pieces()
+            .filter(|(p, _)| p.color == self.side_to_move())
+            .any(|(p, pos)| {
+                self.available_moves_iter(p, pos)
+                    .any(|mv| self.is_capture(mv) && self.is_legal_move(p, mv))
+            })
+    }
+
+    /// Antichess: whether `color` has lost every piece except the king.
+    #[cfg(feature = "antichess")]
+    pub fn has_bare_king(&self, color: PieceColor) -> bool {
+        self.pieces()
+            .all(|(p, _)| p.color != color || p.kind == PieceType::King)
+    }
+
+    /// All legal moves of the side to move.
+    pub fn all_legal_moves(&self) -> Vec<Move> {
+        let moves = self
             .pieces()
-            .filter(|(p, _)| p.color == checkmated_side)
-            .map(|(p, pos)| self.legal_moves(p, pos))
-            .flatten()
-            .count();
-        self.is_king_attacked(checkmated_side) && total_legal_moves == 0
+            .filter(|(p, _)| p.color == self.side_to_move())
+            .flat_map(|(p, pos)| self.legal_moves(p, pos));
+        #[cfg(feature = "crazyhouse")]
+        let moves = moves.chain(self.drop_moves());
+        moves.collect()
+    }
+
+    /// Crazyhouse: switches the drop rules on or off. Off, captures don't fill the hands
+    /// and there are no drops, as in standard chess.
+    #[cfg(feature = "crazyhouse")]
+    pub fn set_crazyhouse(&mut self, enabled: bool) {
+        self.crazyhouse = enabled;
+    }
+
+    /// Crazyhouse: pieces captured by `color`, available for dropping.
+    #[cfg(feature = "crazyhouse")]
+    pub fn hand(&self, color: PieceColor) -> &[PieceType] {
+        match color {
+            PieceColor::White => &self.hands[0],
+            PieceColor::Black => &self.hands[1],
+        }
+    }
+
+    #[cfg(feature = "crazyhouse")]
+    fn hand_mut(&mut self, color: PieceColor) -> &mut Vec<PieceType> {
+        match color {
+            PieceColor::White => &mut self.hands[0],
+            PieceColor::Black => &mut self.hands[1],
+        }
+    }
+
+    /// Crazyhouse: puts a piece into the hand of `color`, for setting up positions.
+    #[cfg(feature = "crazyhouse")]
+    pub fn add_to_hand(&mut self, color: PieceColor, kind: PieceType) {
+        self.hand_mut(color).push(kind);
+    }
+
+    /// Crazyhouse: legal drops of the side to move, none unless the rules are on. Pawns
+    /// can't be dropped on the first and the last rank.
+    #[cfg(feature = "crazyhouse")]
+    pub fn drop_moves(&self) -> Vec<Move> {
+        if !self.crazyhouse {
+            return Vec::new();
+        }
+        let mut kinds = self.hand(self.side_to_move()).to_vec();
+        kinds.sort_by_key(|kind| *kind as u8);
+        kinds.dedup();
+        kinds
+            .into_iter()
+            .flat_map(|piece| {
+                Pos::all()
+                    .filter(move |to| {
+                        piece != PieceType::Pawn
+                            || (to.rank != Rank::new(1) && to.rank != Rank::new(8))
+                    })
+                    .map(move |to| Move::Drop { piece, to })
+            })
+            .filter(|mv| match mv {
+                Move::Drop { to, .. } => !self.is_square_occupied(*to),
+                _ => false,
+            })
+            .filter(|mv| !self.is_check(*mv))
+            .collect()
+    }
+
+    /// Number of leaf positions after `depth` plies, for validating move generation.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.all_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .into_iter()
+            .map(|mv| {
+                let mut next = self.clone();
+                next.apply_move(mv);
+                next.perft(depth - 1)
+            })
+            .sum()
+    }
+
+    /// [`BoardState::perft`] split by the first move, for comparing against other engines.
+    pub fn perft_divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        self.all_legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let mut next = self.clone();
+                next.apply_move(mv);
+                (mv, next.perft(depth - 1))
+            })
+            .collect()
+    }
+
+    /// Legal captures, promotions and checks of the side to move.
+    pub fn generate_tactical_moves(&self) -> Vec<Move> {
+        self.all_legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                let is_promotion = matches!(
+                    mv,
+                    Move::Regular {
+                        promoted: Some(_),
+                        ..
+                    }
+                );
+                is_promotion || self.is_capture(*mv) || self.move_gives_check(*mv)
+            })
+            .collect()
+    }
+
+    /// The result if the game is over after the last move, `None` while it goes on.
+    ///
+    /// Only covers the endings that follow from the position itself, not
+    /// resignations or agreed draws. Use [`outcome`](Self::outcome) for both.
+    pub fn game_status(&self) -> Option<GameResult> {
+        #[cfg(feature = "antichess")]
+        for winner in [self.side_to_move(), self.opponent()] {
+            if self.antichess && self.has_bare_king(winner) {
+                return Some(GameResult::WinByLosingAllPieces { winner });
+            }
+        }
+        if self.is_checkmate(self.side_to_move()) {
+            Some(GameResult::WinByCheckmate {
+                checkmated_side: self.side_to_move(),
+            })
+        } else if self.is_stalemate() {
+            Some(GameResult::DrawByStalemate)
+        } else if self.is_seventy_five_move_draw() {
+            Some(GameResult::DrawBySeventyFiveMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// How the game ended: the recorded [`game_result`](Self::game_result) if there is
+    /// one, otherwise the ending reached on the board, see [`game_status`](Self::game_status).
+    ///
+    /// The board checks generate the legal moves. Storing the outcome in `game_result`
+    /// after each move makes later calls cheap.
+    pub fn outcome(&self) -> Option<GameResult> {
+        self.game_result.or_else(|| self.game_status())
+    }
+
+    /// Whether the game ended in any way, on the board or by resignation or agreement.
+    pub fn is_game_over(&self) -> bool {
+        self.outcome().is_some()
+    }
+
+    /// Whether the side to move has at least one legal move.
+    pub fn has_legal_move(&self) -> bool {
+        #[cfg(feature = "crazyhouse")]
+        if !self.drop_moves().is_empty() {
+            return true;
+        }
+        self.pieces()
+            .filter(|(p, _)| p.color == self.side_to_move())
+            .any(|(p, pos)| self.legal_moves_iter(p, pos).next().is_some())
+    }
+
+    /// Whether the king of the side to move is attacked.
+    pub fn in_check(&self) -> bool {
+        self.is_king_attacked(self.side_to_move())
+    }
+
+    /// Whether the side to move is in check and `mv` gets it out of check.
+    pub fn move_resolves_check(&self, mv: Move) -> bool {
+        self.in_check() && !self.is_check(mv)
+    }
+
+    /// Squares the king of the side to move can legally step to, castling aside.
+    pub fn escape_squares_for_king(&self) -> Vec<Pos> {
+        let king = Piece::new(self.side_to_move(), PieceType::King);
+        let pos = match self.pieces_of(king.kind, king.color).next() {
+            Some(pos) => pos,
+            None => return Vec::new(),
+        };
+        self.legal_moves_iter(king, pos)
+            .filter(|mv| matches!(mv, Move::Regular { .. }))
+            .filter_map(|mv| mv.to())
+            .collect()
+    }
+
+    pub fn is_checkmate(&self, checkmated_side: PieceColor) -> bool {
+        checkmated_side == self.side_to_move() && self.in_check() && !self.has_legal_move()
     }
 
     pub fn is_stalemate(&self) -> bool {
-        let total_legal_moves = self
-            .pieces()
-            .filter(|(p, _)| p.color == self.turn)
-            .map(|(p, pos)| self.legal_moves(p, pos))
-            .flatten()
-            .count();
-        !self.is_king_attacked(self.turn) && total_legal_moves == 0
+        !self.in_check() && !self.has_legal_move()
     }
 
     pub fn is_attacked(&self, pos: Pos, by: PieceColor) -> bool {
-        let attacks = self
-            .pieces()
-            .filter(|(p, _)| p.color == by)
-            .map(|(p, pos)| self.available_moves(p, pos))
-            .flatten()
-            .filter(|mv| mv.to() == Some(pos));
-        attacks.count() > 0
+        self.attacks.is_attacked(pos, by)
+    }
+
+    /// Squares of the pieces of `color` that stand between their king and an enemy
+    /// slider, so they can only move along that line.
+    pub fn pinned_pieces(&self, color: PieceColor) -> Vec<Pos> {
+        self.pieces()
+            .filter(|(p, pos)| p.color == color && self.is_pinned(*pos))
+            .map(|(_, pos)| pos)
+            .collect()
+    }
+
+    /// Whether the piece on `pos` is pinned to its king.
+    fn is_pinned(&self, pos: Pos) -> bool {
+        let color = match self.square_by_pos(pos) {
+            Square::Piece(piece) if piece.kind != PieceType::King => piece.color,
+            _ => return false,
+        };
+        let king = match self.pieces_of(PieceType::King, color).next() {
+            Some(king) => king,
+            None => return false,
+        };
+        let (file_step, rank_step) = match king.direction_to(pos) {
+            Some(step) => step,
+            None => return false,
+        };
+        if Pos::between(king, pos)
+            .iter()
+            .any(|p| self.is_square_occupied(*p))
+        {
+            return false;
+        }
+        let start = UnboundedPos::from_pos(pos);
+        let behind = (1..)
+            .map(|i| UnboundedPos {
+                file: start.file + file_step * i,
+                rank: start.rank + rank_step * i,
+            })
+            .map_while(|p| p.to_pos())
+            .find_map(|p| match self.square_by_pos(p) {
+                Square::Piece(piece) => Some(piece),
+                Square::Empty => None,
+            });
+        let is_diagonal = file_step != 0 && rank_step != 0;
+        match behind {
+            Some(piece) if piece.color != color => match piece.kind {
+                PieceType::Queen => true,
+                PieceType::Bishop => is_diagonal,
+                PieceType::Rook => !is_diagonal,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Squares of all `by`-colored pieces attacking `pos`.
+    ///
+    /// Scans outward from `pos` with the attack pattern of every piece kind, attacks are
+    /// symmetric so a piece of that kind found at the end of the scan attacks `pos`.
+    pub fn attackers_of(&self, pos: Pos, by: PieceColor) -> Vec<Pos> {
+        use PieceType::*;
+        [Pawn, Knight, Bishop, Rook, Queen, King]
+            .into_iter()
+            .flat_map(|kind| {
+                // Pawns attack asymmetrically, so scan with the pawn of the other color.
+                let probe = Piece { color: !by, kind };
+                self.attacked_squares(probe, pos)
+                    .into_iter()
+                    .filter(move |p| {
+                        self.square_by_pos(*p) == Square::Piece(Piece { color: by, kind })
+                    })
+            })
+            .collect()
+    }
+
+    /// Bit `i` is set if the square with index `i` is attacked by `by`.
+    pub fn attacked_bitboard(&self, by: PieceColor) -> u64 {
+        self.attacks.bitboard(by)
     }
 
     pub fn is_king_attacked(&self, king_color: PieceColor) -> bool {
-        let (_, king_pos) = self
-            .pieces()
-            .filter(|(p, _)| p.color == king_color && matches!(p.kind, PieceType::King))
-            .next()
+        let king_pos = self
+            .find_piece(Piece::new(king_color, PieceType::King))
             .unwrap();
         self.is_attacked(king_pos, !king_color)
     }
 
     pub fn is_check(&self, mv: Move) -> bool {
-        let side = self.turn;
+        let side = self.side_to_move();
         let mut next_state = self.clone();
-        next_state.make_move(mv);
+        next_state.apply_move(mv);
         next_state.is_king_attacked(side)
     }
 
+    /// Whether the move puts the opponent's king in check.
+    pub fn move_gives_check(&self, mv: Move) -> bool {
+        let mut next_state = self.clone();
+        next_state.apply_move(mv);
+        next_state.is_king_attacked(self.opponent())
+    }
+
     pub fn pieces(&self) -> impl Iterator<Item = (Piece, Pos)> + '_ {
-        self.squares.iter().enumerate().filter_map(|(i, (s, _))| {
+        self.squares.iter().enumerate().filter_map(|(i, s)| {
             if let Square::Piece(piece) = s {
-                Some((*piece, self.pos_by_square_index(i)))
+                Some((*piece, Self::pos_by_square_index(i)))
             } else {
                 None
             }
         })
     }
 
+    /// Squares of the pieces of the given kind and color.
+    pub fn pieces_of(&self, kind: PieceType, color: PieceColor) -> impl Iterator<Item = Pos> + '_ {
+        self.pieces()
+            .filter(move |(p, _)| p.kind == kind && p.color == color)
+            .map(|(_, pos)| pos)
+    }
+
+    /// The square of the first `piece` found, going from a8 to h1.
+    pub fn find_piece(&self, piece: Piece) -> Option<Pos> {
+        self.pieces_of(piece.kind, piece.color).next()
+    }
+
+    /// Squares of every `piece` on the board.
+    pub fn find_pieces(&self, piece: Piece) -> Vec<Pos> {
+        self.pieces_of(piece.kind, piece.color).collect()
+    }
+
     pub fn is_square_occupied_by_color(&self, pos: Pos, color: PieceColor) -> bool {
         if let Square::Piece(Piece { color: c, .. }) = self.square_by_pos(pos) {
             c == color
@@ -669,32 +1640,1051 @@ impl BoardState {
         matches!(self.square_by_pos(pos), Square::Piece(_))
     }
 
-    pub fn is_highlighted(&self, index: usize) -> bool {
-        self.squares.get(index).unwrap().1
+    pub fn highlights(&self) -> &[MoveTarget] {
+        &self.highlights
+    }
+
+    /// The square at `index` of `squares`, see [`SquareIndex`].
+    pub fn pos_by_square_index(index: usize) -> Pos {
+        let index = SquareIndex::try_from(index).expect("Attempt to get pos by invalid index");
+        index.into()
     }
 
-    fn pos_by_square_index(&self, index: usize) -> Pos {
-        assert!(index < 64, "Attempt to get pos by invalid index");
-        let file = index % 8 + 1;
-        let rank = 8 - index / 8;
-        let file = File::from_u8(file as u8);
-        let rank = Rank::new(rank as u8);
-        Pos::new(file, rank)
+    /// The piece on a square given in algebraic notation, like `"e4"`.
+    /// `None` for an empty square or an invalid string.
+    pub fn at(&self, square: &str) -> Option<Piece> {
+        match self.square_by_pos(square.parse().ok()?) {
+            Square::Piece(piece) => Some(piece),
+            Square::Empty => None,
+        }
     }
 
     pub fn square_by_pos(&self, pos: Pos) -> Square {
-        let index = Self::square_index_by_pos(pos);
-        self.squares[index].0
+        self.squares[SquareIndex::from(pos).get()]
+    }
+
+    pub(crate) fn square_by_pos_mut(&mut self, pos: Pos) -> &mut Square {
+        &mut self.squares[SquareIndex::from(pos).get()]
+    }
+
+    /// Inverse of [`BoardState::pos_by_square_index`].
+    pub fn square_index_by_pos(pos: Pos) -> usize {
+        SquareIndex::from(pos).get()
+    }
+}
+
+/// The pieces a pawn can promote to, in the order the moves are generated.
+const PROMOTIONS: [Option<PromotedTo>; 4] = [
+    Some(PromotedTo::Queen),
+    Some(PromotedTo::Bishop),
+    Some(PromotedTo::Rook),
+    Some(PromotedTo::Knight),
+];
+
+fn knight_jumps(origin: UnboundedPos) -> [UnboundedPos; 8] {
+    [
+        origin.up(2).left(1),
+        origin.up(2).right(1),
+        origin.down(2).left(1),
+        origin.down(2).right(1),
+        origin.up(1).left(2),
+        origin.up(1).right(2),
+        origin.down(1).left(2),
+        origin.down(1).right(2),
+    ]
+}
+
+fn king_steps(origin: UnboundedPos) -> [UnboundedPos; 8] {
+    [
+        origin.up(1),
+        origin.down(1),
+        origin.left(1),
+        origin.right(1),
+        origin.up(1).left(1),
+        origin.up(1).right(1),
+        origin.down(1).left(1),
+        origin.down(1).right(1),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_from_placement(placement: &str) -> BoardState {
+        let mut state = BoardState::new();
+        state.squares = parse_fen(placement);
+        state.attacks = state.compute_attacks();
+        state
+    }
+
+    #[test]
+    fn castling_without_rook() {
+        let state = state_from_placement("4k3/8/8/8/8/8/8/R3K3");
+        assert!(state.is_castling_possible(CastlingSide::Short));
+        let castlings: Vec<_> = state
+            .all_legal_moves()
+            .into_iter()
+            .filter(|mv| matches!(mv, Move::Castling { .. }))
+            .collect();
+        assert_eq!(castlings, [Move::castling(CastlingSide::Long)]);
+        assert!(!state.can_castle_now(CastlingSide::Short));
+    }
+
+    #[test]
+    fn pieces_by_kind_and_color() {
+        let state = BoardState::new();
+        let pawns: Vec<_> = state
+            .pieces_of(PieceType::Pawn, PieceColor::White)
+            .collect();
+        assert_eq!(pawns.len(), 8);
+        assert!(pawns.iter().all(|pos| pos.rank == Rank::new(2)));
+        let knights: Vec<_> = state
+            .pieces_of(PieceType::Knight, PieceColor::Black)
+            .map(|pos| pos.to_string())
+            .collect();
+        assert_eq!(knights, ["b8", "g8"]);
+    }
+
+    #[test]
+    fn moves_by_square() {
+        let mut state = BoardState::new();
+        let targets = |state: &BoardState, square: &str| -> Vec<String> {
+            state
+                .moves_for_square(square.parse().unwrap())
+                .iter()
+                .filter_map(|mv| mv.to())
+                .map(|pos| pos.to_string())
+                .collect()
+        };
+        assert_eq!(targets(&state, "g1"), ["f3", "h3"]);
+        assert!(targets(&state, "g8").is_empty());
+        assert!(targets(&state, "e4").is_empty());
+        state.make_move(state.from_uci("e2e4").unwrap());
+        assert!(targets(&state, "g1").is_empty());
+        assert_eq!(targets(&state, "g8").len(), 2);
+    }
+
+    #[test]
+    fn find_pieces() {
+        let state = BoardState::new();
+        let square = |piece| state.find_piece(piece).map(|pos| pos.to_string());
+        assert_eq!(square(Piece::white_king()).as_deref(), Some("e1"));
+        assert_eq!(square(Piece::black_king()).as_deref(), Some("e8"));
+        let pawns: Vec<_> = state
+            .find_pieces(Piece::white_pawn())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(pawns, ["a2", "b2", "c2", "d2", "e2", "f2", "g2", "h2"]);
+        let empty = BoardState::from_fen(crate::fen::KnownPosition::TwoKings.fen()).unwrap();
+        assert_eq!(empty.find_piece(Piece::black_queen()), None);
+        assert!(empty.find_pieces(Piece::white_pawn()).is_empty());
+    }
+
+    #[test]
+    fn lazy_legal_moves() {
+        let state = BoardState::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        for (piece, pos) in state.pieces() {
+            let lazy: Vec<_> = state.legal_moves_iter(piece, pos).collect();
+            let expected: Vec<_> = state
+                .available_moves(piece, pos)
+                .into_iter()
+                .filter(|mv| state.is_legal_move(piece, *mv))
+                .collect();
+            assert_eq!(lazy, expected);
+            assert_eq!(state.legal_moves(piece, pos), expected);
+        }
+        assert!(state.has_legal_move());
+    }
+
+    #[test]
+    fn distinct_promotions() {
+        let state = BoardState::from_fen("k7/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let pawn = state.at("e7").unwrap();
+        let moves = state.available_moves(pawn, "e7".parse().unwrap());
+        let queen = state
+            .legal_move_between("e7".parse().unwrap(), "e8".parse().unwrap())
+            .unwrap();
+        assert_eq!(queen.promoted(), Some(PromotedTo::Queen));
+        let promotions: Vec<_> = [
+            PromotedTo::Queen,
+            PromotedTo::Rook,
+            PromotedTo::Bishop,
+            PromotedTo::Knight,
+        ]
+        .into_iter()
+        .map(|piece| queen.with_promotion(piece))
+        .collect();
+        for mv in &promotions {
+            assert!(moves.contains(mv));
+            assert_eq!(mv.to(), queen.to());
+            assert_eq!(promotions.iter().filter(|other| *other == mv).count(), 1);
+        }
+        assert!(!moves.contains(&Move::new("e7".parse().unwrap(), "e8".parse().unwrap())));
+        assert_eq!(state.from_uci("e7e8n"), Some(promotions[3]));
+    }
+
+    #[test]
+    fn single_king_escape() {
+        let state = BoardState::from_fen("4k3/8/8/8/8/8/5PP1/r5K1 w - - 0 1").unwrap();
+        let h2: Pos = "h2".parse().unwrap();
+        assert_eq!(state.escape_squares_for_king(), [h2]);
+        assert!(state.move_resolves_check(state.from_uci("g1h2").unwrap()));
+        assert!(!state.move_resolves_check(Move::new("g1".parse().unwrap(), "f1".parse().unwrap())));
+        let quiet = BoardState::new();
+        assert!(!quiet.move_resolves_check(quiet.from_uci("e2e4").unwrap()));
+        assert!(quiet.escape_squares_for_king().is_empty());
+    }
+
+    #[test]
+    fn captures() {
+        let mut state = BoardState::new();
+        for uci in ["e2e4", "a7a6", "e4e5", "d7d5"] {
+            state.make_move(state.from_uci(uci).unwrap());
+        }
+        let e5 = Pos::new(File::E, Rank::new(5));
+        let d6 = Pos::new(File::D, Rank::new(6));
+        let en_passant = Move::new(e5, d6);
+        assert!(!state.is_square_occupied(d6));
+        assert!(state.is_capture(en_passant));
+        assert!(!state.is_capture(Move::new(e5, Pos::new(File::E, Rank::new(6)))));
+        state.make_move(en_passant);
+        assert!(!state.is_square_occupied(Pos::new(File::D, Rank::new(5))));
+        assert_eq!(state.plies_since_last_non_repeatable_move(), 0);
+        let b8 = Pos::new(File::B, Rank::new(8));
+        let d7 = Pos::new(File::D, Rank::new(7));
+        assert!(!state.is_capture(Move::new(b8, d7)));
+        assert!(state.is_capture(Move::new(Pos::new(File::C, Rank::new(7)), d6)));
+    }
+
+    #[test]
+    fn pawn_captures_at_the_edges() {
+        let uci_moves = |state: &BoardState, from: &str| -> Vec<String> {
+            let piece = state.at(from).unwrap();
+            let mut moves: Vec<_> = state
+                .available_moves(piece, from.parse().unwrap())
+                .into_iter()
+                .map(|mv| state.to_uci(mv))
+                .collect();
+            moves.sort();
+            moves
+        };
+        // Black pieces where a capture wrapping around the board would land.
+        let state = BoardState::from_fen("k7/8/n6n/nn4nn/P6P/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(uci_moves(&state, "a4"), ["a4b5"]);
+        assert_eq!(uci_moves(&state, "h4"), ["h4g5"]);
+
+        // Blocked on d8, so only the promotions capturing on c8 and e8.
+        let state = BoardState::from_fen("2nrn2k/3P4/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let promotions = [
+            "d7c8b", "d7c8n", "d7c8q", "d7c8r", "d7e8b", "d7e8n", "d7e8q", "d7e8r",
+        ];
+        assert_eq!(uci_moves(&state, "d7"), promotions);
+        let state = BoardState::from_fen("1n5k/P7/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let promotions = [
+            "a7a8b", "a7a8n", "a7a8q", "a7a8r", "a7b8b", "a7b8n", "a7b8q", "a7b8r",
+        ];
+        assert_eq!(uci_moves(&state, "a7"), promotions);
+    }
+
+    #[test]
+    fn capture_and_quiet_targets() {
+        let state = state_from_placement("7k/8/8/p7/8/8/8/R6K");
+        let rook = Piece {
+            kind: PieceType::Rook,
+            color: PieceColor::White,
+        };
+        let targets = state.move_targets(rook, Pos::new(File::A, Rank::new(1)));
+        assert_eq!(targets.len(), 10);
+        let kind_at = |pos: Pos| targets.iter().find(|t| t.to == pos).map(|t| t.kind);
+        assert_eq!(
+            kind_at(Pos::new(File::A, Rank::new(5))),
+            Some(HighlightKind::Capture)
+        );
+        assert_eq!(
+            kind_at(Pos::new(File::A, Rank::new(4))),
+            Some(HighlightKind::Move)
+        );
+        assert_eq!(
+            kind_at(Pos::new(File::G, Rank::new(1))),
+            Some(HighlightKind::Move)
+        );
+        assert_eq!(kind_at(Pos::new(File::A, Rank::new(6))), None);
+    }
+
+    fn without_castling(mut state: BoardState) -> BoardState {
+        state.castling_white.king_moved = true;
+        state.castling_black.king_moved = true;
+        state
+    }
+
+    #[test]
+    fn no_legal_moves_only_in_mate_or_stalemate() {
+        let start = BoardState::new();
+        assert!(start.has_legal_move());
+        assert!(!start.is_checkmate(PieceColor::White) && !start.is_stalemate());
+
+        let mate = without_castling(state_from_placement("8/8/8/8/8/6k1/6q1/7K"));
+        assert!(!mate.has_legal_move());
+        assert!(mate.is_checkmate(PieceColor::White));
+
+        let stalemate = without_castling(state_from_placement("8/8/8/8/8/6k1/5q2/7K"));
+        assert!(!stalemate.has_legal_move());
+        assert!(stalemate.is_stalemate());
+
+        let escape = without_castling(state_from_placement("8/8/8/8/8/8/8/k3r2K"));
+        assert!(escape.in_check());
+        assert!(escape.has_legal_move());
+    }
+
+    #[test]
+    fn checking_moves() {
+        let state = state_from_placement("7k/8/8/8/8/8/8/R6K");
+        let a1 = Pos::new(File::A, Rank::new(1));
+        assert!(state.move_gives_check(Move::new(a1, Pos::new(File::A, Rank::new(8)))));
+        assert!(!state.move_gives_check(Move::new(a1, Pos::new(File::A, Rank::new(7)))));
+        let rook = Piece {
+            kind: PieceType::Rook,
+            color: PieceColor::White,
+        };
+        let checks: Vec<_> = state
+            .move_targets(rook, a1)
+            .into_iter()
+            .filter(|t| t.gives_check)
+            .map(|t| t.to)
+            .collect();
+        assert_eq!(checks, vec![Pos::new(File::A, Rank::new(8))]);
+    }
+
+    #[test]
+    fn square_index_roundtrip() {
+        let mut indices: Vec<_> = Pos::all()
+            .map(|pos| {
+                let index = BoardState::square_index_by_pos(pos);
+                assert_eq!(BoardState::pos_by_square_index(index), pos);
+                index
+            })
+            .collect();
+        indices.sort();
+        assert_eq!(indices, (0..64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn incremental_attacks() {
+        let mut state = BoardState::new();
+        let moves = [
+            "e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5", "e1g1", "g8f6", "f3e5", "c6e5", "d2d4",
+            "c5d4", "d1d4", "e5c4", "d4c4",
+        ];
+        for uci in moves {
+            let mv = state.from_uci(uci).unwrap();
+            state.make_move(mv);
+            assert_eq!(state.attacks, state.compute_attacks(), "after {uci}");
+        }
+        // Taking back restores the maps, checked against a replay from the start.
+        for (played, uci) in moves.iter().enumerate().rev() {
+            assert!(state.unmake_move());
+            assert_eq!(state.attacks, state.compute_attacks(), "taking back {uci}");
+            let mut replayed = BoardState::new();
+            for uci in &moves[..played] {
+                replayed.make_move(replayed.from_uci(uci).unwrap());
+            }
+            assert_eq!(state.attacks, replayed.attacks, "taking back {uci}");
+        }
+        assert!(!state.unmake_move());
+    }
+
+    #[test]
+    fn pgn_result_tokens() {
+        use PieceColor::*;
+        let cases = [
+            (
+                GameResult::WinByCheckmate {
+                    checkmated_side: Black,
+                },
+                "1-0",
+                "White wins by checkmate",
+            ),
+            (
+                GameResult::WinByCheckmate {
+                    checkmated_side: White,
+                },
+                "0-1",
+                "Black wins by checkmate",
+            ),
+            (
+                GameResult::Resignation {
+                    resigned_side: White,
+                },
+                "0-1",
+                "Black wins by resignation",
+            ),
+            (
+                GameResult::Resignation {
+                    resigned_side: Black,
+                },
+                "1-0",
+                "White wins by resignation",
+            ),
+            (
+                GameResult::Timeout {
+                    flagged_side: White,
+                },
+                "0-1",
+                "Black wins on time",
+            ),
+            (
+                GameResult::Timeout {
+                    flagged_side: Black,
+                },
+                "1-0",
+                "White wins on time",
+            ),
+            (GameResult::DrawByStalemate, "1/2-1/2", "Draw by stalemate"),
+            (GameResult::DrawByAgreement, "1/2-1/2", "Draw by agreement"),
+            (
+                GameResult::DrawByFiftyMoveRule,
+                "1/2-1/2",
+                "Draw by the fifty-move rule",
+            ),
+            (
+                GameResult::DrawBySeventyFiveMoveRule,
+                "1/2-1/2",
+                "Draw by the seventy-five-move rule",
+            ),
+            (
+                GameResult::DrawByThreefoldRepetition,
+                "1/2-1/2",
+                "Draw by threefold repetition",
+            ),
+            (
+                GameResult::DrawByInsufficientMaterial,
+                "1/2-1/2",
+                "Draw by insufficient material",
+            ),
+        ];
+        for (result, token, description) in cases {
+            assert_eq!(result.pgn_token(), token);
+            assert_eq!(result.description(), description);
+            assert_eq!(result.to_string(), description);
+        }
+    }
+
+    #[test]
+    fn capturing_check_event() {
+        let mut state = state_from_placement("4k3/8/8/4n3/8/8/4R3/K7");
+        let mv = Move::new(
+            Pos::new(File::E, Rank::new(2)),
+            Pos::new(File::E, Rank::new(5)),
+        );
+        assert_eq!(state.make_move(mv), MoveEvent::Check);
+    }
+
+    #[test]
+    fn premove_legality() {
+        let mut state = BoardState::new();
+        let e2 = Pos::new(File::E, Rank::new(2));
+        let e4 = Pos::new(File::E, Rank::new(4));
+        let e5 = Pos::new(File::E, Rank::new(5));
+        let e7 = Pos::new(File::E, Rank::new(7));
+        state.make_move(Move::new(e2, e4));
+        state.make_move(Move::new(e7, e5));
+        // The pawn is blocked now, so a queued e4-e5 is dropped.
+        assert_eq!(state.legal_move_between(e4, e5), None);
+        let g1 = Pos::new(File::G, Rank::new(1));
+        let f3 = Pos::new(File::F, Rank::new(3));
+        assert_eq!(state.legal_move_between(g1, f3), Some(Move::new(g1, f3)));
+        // Can't premove with the opponent's pieces.
+        assert_eq!(
+            state.legal_move_between(e5, Pos::new(File::E, Rank::new(4))),
+            None
+        );
+    }
+
+    #[test]
+    fn uci_roundtrip() {
+        let mut state = state_from_placement("4k3/1P6/8/8/8/8/8/4K2R");
+        let promotion = state.from_uci("b7b8n").unwrap();
+        assert_eq!(
+            promotion,
+            Move::new_with_promoted(
+                Pos::new(File::B, Rank::new(7)),
+                Pos::new(File::B, Rank::new(8)),
+                Some(PromotedTo::Knight)
+            )
+        );
+        assert_eq!(state.to_uci(promotion), "b7b8n");
+        let castling = state.from_uci("e1g1").unwrap();
+        assert_eq!(castling, Move::castling(CastlingSide::Short));
+        assert_eq!(state.to_uci(castling), "e1g1");
+        assert_eq!(state.from_uci("e1e3"), None);
+        assert_eq!(state.from_uci("b7b8k"), None);
+        state.make_move(castling);
+        assert_eq!(
+            state.from_uci("e8d8"),
+            Some(Move::new(
+                Pos::new(File::E, Rank::new(8)),
+                Pos::new(File::D, Rank::new(8)),
+            ))
+        );
+    }
+
+    #[test]
+    fn castling_right_now() {
+        let p = |s: &str| -> Pos { s.parse().unwrap() };
+        let pc = |kind, color| Piece { kind, color };
+        let rooks = crate::board::BoardBuilder::new()
+            .piece(p("e1"), pc(PieceType::King, PieceColor::White))
+            .piece(p("h1"), pc(PieceType::Rook, PieceColor::White))
+            .piece(p("a1"), pc(PieceType::Rook, PieceColor::White))
+            .piece(p("b8"), pc(PieceType::King, PieceColor::Black))
+            .castling(PieceColor::White, CastlingSide::Short)
+            .castling(PieceColor::White, CastlingSide::Long);
+        let clear = rooks.clone().build().unwrap();
+        assert!(clear.can_castle_now(CastlingSide::Short));
+        assert!(clear.can_castle_now(CastlingSide::Long));
+
+        let attacked = rooks
+            .clone()
+            .piece(p("f8"), pc(PieceType::Rook, PieceColor::Black))
+            .build()
+            .unwrap();
+        assert!(attacked.is_castling_possible(CastlingSide::Short));
+        assert!(!attacked.can_castle_now(CastlingSide::Short));
+        assert!(attacked.can_castle_now(CastlingSide::Long));
+
+        let in_check = rooks
+            .piece(p("e5"), pc(PieceType::Rook, PieceColor::Black))
+            .build()
+            .unwrap();
+        assert!(!in_check.can_castle_now(CastlingSide::Short));
+        assert!(!in_check.can_castle_now(CastlingSide::Long));
+    }
+
+    #[test]
+    fn san_ignores_pinned_duplicate() {
+        let b1: Pos = "b1".parse().unwrap();
+        let d2: Pos = "d2".parse().unwrap();
+        let pinned = BoardState::from_fen("k7/8/8/8/8/8/8/1N2rN1K w - - 0 1").unwrap();
+        let san = pinned.to_san_move(Move::new(b1, d2));
+        assert_eq!(san.to_string(), "Nd2");
+
+        let free = BoardState::from_fen("k3r3/8/8/8/8/8/8/1N3N1K w - - 0 1").unwrap();
+        let san = free.to_san_move(Move::new(b1, d2));
+        assert_eq!(san.to_string(), "Nbd2");
+    }
+
+    #[test]
+    fn tactical_moves() {
+        let state = BoardState::from_fen("4k3/pP6/8/8/8/8/3n4/R3K2R w K - 0 1").unwrap();
+        let mut moves: Vec<_> = state
+            .generate_tactical_moves()
+            .into_iter()
+            .map(|mv| state.to_uci(mv))
+            .collect();
+        moves.sort();
+        let expected = ["a1a7", "b7b8b", "b7b8n", "b7b8q", "b7b8r", "e1d2", "h1h8"];
+        assert_eq!(moves, expected);
+    }
+
+    #[test]
+    fn piece_at() {
+        let state = BoardState::new();
+        let king = Piece {
+            kind: PieceType::King,
+            color: PieceColor::White,
+        };
+        assert_eq!(state.at("e1"), Some(king));
+        assert_eq!(state.at("e4"), None);
+        assert_eq!(state.at("z9"), None);
+    }
+
+    #[test]
+    fn attackers_and_defenders() {
+        // The knight on d5 is defended by the pawn and the rook, the rook on d1 is blocked.
+        let state = BoardState::from_fen("3r3k/8/4p3/3n4/8/2N5/3P4/3R3K w - - 0 1").unwrap();
+        let d5: Pos = "d5".parse().unwrap();
+        let attackers = state.attackers_of(d5, PieceColor::White);
+        assert_eq!(attackers, ["c3".parse::<Pos>().unwrap()]);
+        let mut defenders: Vec<_> = state
+            .attackers_of(d5, PieceColor::Black)
+            .into_iter()
+            .map(|pos| pos.to_string())
+            .collect();
+        defenders.sort();
+        assert_eq!(defenders, ["d8", "e6"]);
+    }
+
+    #[test]
+    fn knight_moves_on_every_square() {
+        let mut state = BoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        // Without the kings nothing blocks the knight.
+        for king in ["e1", "e8"] {
+            *state.square_by_pos_mut(king.parse().unwrap()) = Square::Empty;
+        }
+        let knight = Piece::new(PieceColor::White, PieceType::Knight);
+        for pos in Pos::all() {
+            // Distance from the nearest edge along each axis, capped at 2.
+            let file = (pos.file.as_u8() - 1).min(8 - pos.file.as_u8()).min(2);
+            let rank = (pos.rank.get() - 1).min(8 - pos.rank.get()).min(2);
+            let expected = match (file.min(rank), file.max(rank)) {
+                (0, 0) => 2,
+                (0, 1) => 3,
+                (0, 2) | (1, 1) => 4,
+                (1, 2) => 6,
+                _ => 8,
+            };
+            let moves = state.available_moves(knight, pos);
+            assert_eq!(moves.len(), expected, "knight on {pos}");
+        }
+        let g1: Pos = "g1".parse().unwrap();
+        assert_eq!(g1.left(2), "e1".parse().unwrap());
+        assert_eq!(g1.right(1).up(2), "h3".parse().unwrap());
+    }
+
+    #[test]
+    fn promotion_with_capture() {
+        let fen = "3rk3/4P3/8/8/8/8/8/4K3 w - - 7 40";
+        let mut state = BoardState::from_fen(fen).unwrap();
+        #[cfg(feature = "crazyhouse")]
+        state.set_crazyhouse(true);
+        let mv = state.from_san_move("exd8=Q".parse().unwrap()).unwrap();
+        assert!(state.is_capture(mv));
+        state.make_move(mv);
+        assert_eq!(
+            state.square_by_pos("d8".parse().unwrap()),
+            Square::Piece(Piece::new(PieceColor::White, PieceType::Queen))
+        );
+        assert_eq!(state.square_by_pos("e7".parse().unwrap()), Square::Empty);
+        assert_eq!(state.plies_since_last_non_repeatable_move(), 0);
+        assert_eq!(state.to_fen(), "3Qk3/8/8/8/8/8/8/4K3 b - - 0 40");
+        assert!(state.in_check());
+        #[cfg(feature = "crazyhouse")]
+        assert_eq!(state.hand(PieceColor::White), [PieceType::Rook]);
+        assert!(state.unmake_move());
+        assert_eq!(state.to_fen(), fen);
+    }
+
+    #[test]
+    fn en_passant_target() {
+        let mut state = BoardState::new();
+        state.make_move(state.from_uci("e2e4").unwrap());
+        assert_eq!(state.en_passant(), Some("e3".parse().unwrap()));
+        state.make_move(state.from_uci("g8f6").unwrap());
+        assert_eq!(state.en_passant(), None);
+        state.make_move(state.from_uci("e4e5").unwrap());
+        assert_eq!(state.en_passant(), None);
+        state.make_move(state.from_uci("d7d5").unwrap());
+        assert_eq!(state.en_passant(), Some("d6".parse().unwrap()));
+        assert!(state.from_uci("e5d6").is_some());
+    }
+
+    #[test]
+    fn san_roundtrip() {
+        let fens = crate::fen::KnownPosition::ALL
+            .map(crate::fen::KnownPosition::fen)
+            .into_iter()
+            .chain([
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                // Queens sharing files and ranks: Qc6-c4 needs the rank, Qc6-e4 the square.
+                "6k1/8/2Q5/8/4Q3/8/2Q5/4K3 w - - 0 1",
+                "7k/8/2Q1Q3/8/8/8/2Q5/4K3 w - - 0 1",
+            ]);
+        for fen in fens {
+            let state = BoardState::from_fen(fen).unwrap();
+            let replies = state.all_legal_moves().into_iter().map(|mv| {
+                let mut next = state.clone();
+                next.make_move(mv);
+                next
+            });
+            for state in std::iter::once(state.clone()).chain(replies) {
+                for mv in state.all_legal_moves() {
+                    let san = state.to_san_move(mv);
+                    assert_eq!(
+                        state.from_san_move(san),
+                        Ok(mv),
+                        "{san} in {}",
+                        state.to_fen()
+                    );
+                }
+            }
+        }
+        let state = BoardState::from_fen("6k1/8/2Q5/8/4Q3/8/2Q5/4K3 w - - 0 1").unwrap();
+        let san = |uci| state.to_san_move(state.from_uci(uci).unwrap()).to_string();
+        assert_eq!(san("c6c4"), "Q6c4");
+        assert_eq!(san("e4c4"), "Qec4");
+        let state = BoardState::from_fen("7k/8/2Q1Q3/8/8/8/2Q5/4K3 w - - 0 1").unwrap();
+        let san = |uci| state.to_san_move(state.from_uci(uci).unwrap()).to_string();
+        assert_eq!(san("c6e4"), "Qc6e4");
+    }
+
+    #[test]
+    fn unresolved_san() {
+        let resolve = |fen: &str, san: &str| {
+            let state = BoardState::from_fen(fen).unwrap();
+            state
+                .from_san_move(san.parse().unwrap())
+                .map(|mv| state.to_uci(mv))
+        };
+        let start = crate::fen::KnownPosition::Starting.fen();
+        assert_eq!(resolve(start, "Nf3").as_deref(), Ok("g1f3"));
+        assert_eq!(resolve(start, "Nd4"), Err(SanResolveError::IllegalMove));
+        assert_eq!(resolve(start, "e5"), Err(SanResolveError::IllegalMove));
+        assert_eq!(resolve(start, "O-O"), Err(SanResolveError::IllegalMove));
+        assert_eq!(
+            resolve(start, "Nhf3"),
+            Err(SanResolveError::NoMatchingPiece)
+        );
+        let knights = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+        assert_eq!(
+            resolve(knights, "Qd4"),
+            Err(SanResolveError::NoMatchingPiece)
+        );
+        assert_eq!(resolve(knights, "Nd2"), Err(SanResolveError::Ambiguous));
+        assert_eq!(resolve(knights, "Nbd2").as_deref(), Ok("b1d2"));
+        // A promotion must name the piece.
+        let promotion = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(resolve(promotion, "a8"), Err(SanResolveError::IllegalMove));
+        assert_eq!(resolve(promotion, "a8=N").as_deref(), Ok("a7a8n"));
+    }
+
+    #[test]
+    fn san_strings() {
+        let mut state = BoardState::new();
+        let mut sans = Vec::new();
+        let moves = [
+            "g1f3", "d7d5", "e2e4", "e7e6", "e4d5", "f8c5", "f1c4", "g8f6", "e1g1", "c5f2",
+        ];
+        for uci in moves {
+            let mv = state.from_uci(uci).unwrap();
+            sans.push(state.san(mv));
+            state.make_move(mv);
+        }
+        assert_eq!(
+            sans,
+            ["Nf3", "d5", "e4", "e6", "exd5", "Bc5", "Bc4", "Nf6", "O-O", "Bxf2+"]
+        );
+        let scholars = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        let state = BoardState::from_fen(scholars).unwrap();
+        let before = state.to_fen();
+        assert_eq!(state.san(state.from_uci("h5f7").unwrap()), "Qxf7#");
+        assert_eq!(state.to_fen(), before);
+    }
+
+    #[test]
+    fn halfmove_clock() {
+        let mut state = BoardState::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        state.set_halfmove_clock(99);
+        assert!(!state.is_fifty_move_draw());
+        let mut pawn_move = state.clone();
+        pawn_move.make_move(state.from_uci("e2e4").unwrap());
+        assert!(!pawn_move.is_fifty_move_draw());
+        state.make_move(state.from_uci("e1d1").unwrap());
+        assert!(state.is_fifty_move_draw());
+        assert_eq!(state.to_fen(), "4k3/8/8/8/8/8/4P3/3K4 b - - 100 1");
+    }
+
+    #[test]
+    fn seventy_five_move_rule() {
+        let mut state = BoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 148 90").unwrap();
+        assert!(state.is_fifty_move_draw());
+        let event = state.make_move(state.from_uci("e1d1").unwrap());
+        assert_eq!(event, MoveEvent::Move);
+        assert!(!state.is_seventy_five_move_draw());
+        let event = state.make_move(state.from_uci("e8d8").unwrap());
+        assert_eq!(event, MoveEvent::GameEnd);
+        assert!(state.is_seventy_five_move_draw());
+    }
+
+    #[test]
+    fn take_back() {
+        use crate::fen::KnownPosition;
+        // Every move of Kiwipete, with castling, captures and en passant to undo.
+        let kiwipete = BoardState::from_fen(KnownPosition::Kiwipete.fen()).unwrap();
+        for mv in kiwipete.all_legal_moves() {
+            let mut state = kiwipete.clone();
+            state.make_move(mv);
+            let reply = state.all_legal_moves()[0];
+            state.make_move(reply);
+            assert!(state.unmake_move());
+            assert!(state.unmake_move());
+            assert_eq!(state.to_fen(), kiwipete.to_fen(), "{mv:?}");
+            assert_eq!(state.position_hash(), kiwipete.position_hash());
+            assert_eq!(state.perft(1), kiwipete.perft(1));
+        }
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 3 7";
+        let mut state = BoardState::from_fen(fen).unwrap();
+        state.make_move(state.from_uci("e5f6").unwrap());
+        state.make_move(state.from_uci("e8f7").unwrap());
+        assert!(state.unmake_move());
+        assert!(state.unmake_move());
+        assert_eq!(state.to_fen(), fen);
+        assert_eq!(state.repetition_count(), 1);
+        assert!(!state.unmake_move());
+    }
+
+    #[test]
+    fn perft_counts() {
+        use crate::fen::KnownPosition;
+        assert_eq!(BoardState::new().perft(2), 400);
+        let kiwipete = BoardState::from_fen(KnownPosition::Kiwipete.fen()).unwrap();
+        assert_eq!(kiwipete.perft(1), 48);
+        assert_eq!(kiwipete.perft(2), 2039);
+        let divide = kiwipete.perft_divide(2);
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), 2039);
+    }
+
+    #[test]
+    fn pins() {
+        let state = BoardState::from_fen("4k3/4r3/8/8/1b6/2N5/4B3/4K3 w - - 0 1").unwrap();
+        let pinned: Vec<_> = state
+            .pinned_pieces(PieceColor::White)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(pinned, ["c3", "e2"]);
+        let bishop = state.at("e2").unwrap();
+        assert!(state.legal_moves(bishop, "e2".parse().unwrap()).is_empty());
+        // The position from the Chess Programming Wiki full of pins and en passant
+        // discovered checks, checking the shortcut in is_legal_move.
+        let state = BoardState::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+        assert_eq!(state.perft(1), 14);
+        assert_eq!(state.perft(2), 191);
+    }
+
+    #[test]
+    fn game_from_moves() {
+        let pos = |s: &str| s.parse::<Pos>().unwrap();
+        let moves = [
+            Move::new(pos("e2"), pos("e4")),
+            Move::new(pos("e7"), pos("e5")),
+            Move::new(pos("g1"), pos("f3")),
+        ];
+        let state = BoardState::from_moves(&moves).unwrap();
+        assert_eq!(
+            state.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        // The e4 pawn can't move backwards.
+        let moves = [moves[0], moves[1], Move::new(pos("e4"), pos("e3"))];
+        assert_eq!(BoardState::from_moves(&moves).unwrap_err(), 2);
+    }
+
+    #[test]
+    fn perpetual_check() {
+        let mut state = BoardState::from_fen("7k/6p1/7p/8/8/8/5PPP/4Q1K1 w - - 0 1").unwrap();
+        for uci in ["e1e8", "h8h7", "e8e4", "h7h8"] {
+            state.make_move(state.from_uci(uci).unwrap());
+            assert!(!state.is_perpetual_check_likely());
+        }
+        state.make_move(state.from_uci("e4e8").unwrap());
+        assert_eq!(state.repetition_count(), 2);
+        assert!(state.is_perpetual_check_likely());
+
+        // Repeating without checks.
+        let mut state = BoardState::new();
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            state.make_move(state.from_uci(uci).unwrap());
+        }
+        assert_eq!(state.repetition_count(), 2);
+        assert!(!state.is_perpetual_check_likely());
+    }
+
+    #[test]
+    fn repetitions_after_irreversible_moves() {
+        let mut state = BoardState::new();
+        let play = |state: &mut BoardState, moves: &[&str]| {
+            for uci in moves {
+                state.make_move(state.from_uci(uci).unwrap());
+            }
+        };
+        let knights = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        play(&mut state, &knights);
+        assert_eq!(state.repetition_count(), 2);
+        assert_eq!(state.last_irreversible_ply(), 0);
+        // The earlier positions can't repeat once a pawn moved.
+        play(&mut state, &["e2e4"]);
+        assert_eq!(state.last_irreversible_ply(), 5);
+        assert_eq!(state.repetition_count(), 1);
+        play(&mut state, &["g8f6", "g1f3", "f6g8", "f3g1"]);
+        assert_eq!(state.repetition_count(), 2);
+    }
+
+    #[cfg(feature = "antichess")]
+    #[test]
+    fn antichess_mandatory_captures() {
+        let mut state = BoardState::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(state.all_legal_moves().len(), 7);
+        state.set_antichess(true);
+        let moves: Vec<_> = state
+            .all_legal_moves()
+            .into_iter()
+            .map(|mv| state.to_uci(mv))
+            .collect();
+        assert_eq!(moves, ["e4d5"]);
+        // Without captures every move is allowed.
+        let mut state = BoardState::from_fen("4k3/8/8/8/4P3/8/8/4K3 w - - 0 1").unwrap();
+        state.set_antichess(true);
+        assert!(!state.has_legal_capture());
+        assert_eq!(state.all_legal_moves().len(), 6);
+    }
+
+    #[cfg(feature = "antichess")]
+    #[test]
+    fn antichess_bare_king() {
+        let mut state = BoardState::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        state.set_antichess(true);
+        assert_eq!(state.game_status(), None);
+        state.make_move(state.from_uci("e4d5").unwrap());
+        assert!(state.has_bare_king(PieceColor::Black));
+        assert_eq!(
+            state.game_status(),
+            Some(GameResult::WinByLosingAllPieces {
+                winner: PieceColor::Black
+            })
+        );
+        assert_eq!(state.game_status().unwrap().pgn_token(), "0-1");
     }
 
-    fn square_by_pos_mut(&mut self, pos: Pos) -> &mut Square {
-        let index = Self::square_index_by_pos(pos);
-        &mut self.squares[index].0
+    #[test]
+    fn mirrored_positions() {
+        let fen = "r3k2r/8/8/8/3pP3/8/8/R3K3 b Qkq e3 0 1";
+        let state = BoardState::from_fen(fen).unwrap();
+        let mirror = state.mirror_vertical();
+        assert_eq!(mirror.to_fen(), "r3k3/8/8/3Pp3/8/8/8/R3K2R w KQq e6 0 1");
+        assert_eq!(mirror.material_balance(), -state.material_balance());
+        assert_eq!(mirror.mirror_vertical().to_fen(), fen);
+
+        let mirror = state.mirror_horizontal();
+        assert_eq!(mirror.side_to_move(), PieceColor::Black);
+        assert_eq!(mirror.en_passant(), Some("d3".parse().unwrap()));
+        assert!(!mirror.has_castling_right(PieceColor::Black, CastlingSide::Long));
+        // Back where it started, except for the lost castling rights.
+        assert_eq!(
+            mirror.mirror_horizontal().to_fen(),
+            "r3k2r/8/8/8/3pP3/8/8/R3K3 b - e3 0 1"
+        );
     }
 
-    fn square_index_by_pos(pos: Pos) -> usize {
-        let i = ((64 - (pos.rank.get() * 8)) + pos.file.as_u8() - 1) as usize;
-        assert!(i < 64, "Attempt to access square outside bounds");
-        i
+    #[test]
+    fn side_to_move_and_opponent() {
+        let mut state = BoardState::new();
+        assert_eq!(state.side_to_move(), PieceColor::White);
+        assert_eq!(state.opponent(), PieceColor::Black);
+        state.make_move(state.from_uci("e2e4").unwrap());
+        assert_eq!(state.side_to_move(), PieceColor::Black);
+        assert_eq!(state.opponent(), PieceColor::White);
+        state.make_move(state.from_uci("e7e5").unwrap());
+        assert_eq!(state.side_to_move(), PieceColor::White);
+        assert_eq!(state.opponent(), PieceColor::Black);
+    }
+
+    #[test]
+    fn endgame_statuses() {
+        use crate::fen::KnownPosition;
+        let status =
+            |position: KnownPosition| BoardState::from_fen(position.fen()).unwrap().game_status();
+        assert_eq!(status(KnownPosition::Starting), None);
+        assert_eq!(status(KnownPosition::TwoKings), None);
+        assert_eq!(
+            status(KnownPosition::KingStalemate),
+            Some(GameResult::DrawByStalemate)
+        );
+        let black_mated = Some(GameResult::WinByCheckmate {
+            checkmated_side: PieceColor::Black,
+        });
+        assert_eq!(status(KnownPosition::BackRankMate), black_mated);
+        assert_eq!(status(KnownPosition::SmotheredMate), black_mated);
+
+        // The king can't step next to the other king to escape.
+        let adjacent = BoardState::from_fen("k7/2K5/8/8/8/8/8/Q7 b - - 0 1").unwrap();
+        assert_eq!(adjacent.game_status(), black_mated);
+    }
+
+    #[test]
+    fn game_over() {
+        let mut state = BoardState::new();
+        assert!(!state.is_game_over());
+        assert_eq!(state.outcome(), None);
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            state.make_move(state.from_uci(uci).unwrap());
+        }
+        assert!(state.is_game_over());
+        assert_eq!(
+            state.outcome(),
+            Some(GameResult::WinByCheckmate {
+                checkmated_side: PieceColor::White
+            })
+        );
+        // A recorded result counts even though play could go on.
+        let mut state = BoardState::new();
+        state.game_result = Some(GameResult::DrawByAgreement);
+        assert!(state.is_game_over());
+        assert_eq!(state.outcome(), Some(GameResult::DrawByAgreement));
+    }
+
+    #[cfg(feature = "crazyhouse")]
+    #[test]
+    fn crazyhouse_drops() {
+        let mut state = BoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        state.add_to_hand(PieceColor::White, PieceType::Pawn);
+        // Standard chess unless the rules are switched on.
+        assert!(state.drop_moves().is_empty());
+        state.set_crazyhouse(true);
+        state.add_to_hand(PieceColor::White, PieceType::Pawn);
+        // 62 empty squares, minus 14 more on the first and last ranks.
+        assert_eq!(state.drop_moves().len(), 48);
+        state.add_to_hand(PieceColor::White, PieceType::Knight);
+        assert_eq!(state.drop_moves().len(), 48 + 62);
+
+        let drop = Move::Drop {
+            piece: PieceType::Pawn,
+            to: "e4".parse().unwrap(),
+        };
+        assert_eq!(state.to_san_move(drop).to_string(), "P@e4");
+        state.make_move(drop);
+        assert_eq!(
+            state.hand(PieceColor::White),
+            &[PieceType::Pawn, PieceType::Knight]
+        );
+        assert_eq!(state.at("e4").map(|p| p.kind), Some(PieceType::Pawn));
+
+        // Captured pieces go to the capturer's hand.
+        state.make_move(state.from_uci("e8d7").unwrap());
+        state.make_move(Move::Drop {
+            piece: PieceType::Knight,
+            to: "c5".parse().unwrap(),
+        });
+        state.make_move(state.from_uci("d7c6").unwrap());
+        state.make_move(state.from_uci("e1d1").unwrap());
+        state.make_move(state.from_uci("c6c5").unwrap());
+        assert_eq!(state.hand(PieceColor::Black), &[PieceType::Knight]);
+    }
+
+    #[cfg(feature = "crazyhouse")]
+    #[test]
+    fn crazyhouse_san() {
+        let e4 = "e4".parse().unwrap();
+        let pawn_drop = SanMove::Drop {
+            piece: PieceType::Pawn,
+            to: e4,
+        };
+        assert_eq!("P@e4".parse::<SanMove>(), Ok(pawn_drop));
+        assert_eq!(pawn_drop.to_string(), "P@e4");
+        assert_eq!("N@f3+".parse::<SanMove>().unwrap().to_string(), "N@f3");
+        assert!("X@e4".parse::<SanMove>().is_err());
+        let drop = Move::Drop {
+            piece: PieceType::Pawn,
+            to: e4,
+        };
+        assert_eq!(Move::from_compact(&drop.to_compact()), Some(drop));
     }
 }
@@ -0,0 +1,1398 @@
+use std::rc::Rc;
+
+use chess::board::{BoardState, GameResult, Handles, HighlightKind, Square};
+use chess::engine::{computer_move, Difficulty};
+use chess::moves::internal::Move;
+use chess::moves::MoveEvent;
+use chess::moves::PromotedTo;
+use chess::pgn::{from_pgn_collection, replay, start_position, Game, PgnError};
+use chess::piece::{Piece, PieceColor};
+use chess::pos::{File, Pos, Rank};
+use chess::puzzle::{submit_move, Puzzle, PuzzleOutcome};
+use chess::render::{
+    arrow_endpoints, oriented_square_coords, square_color, square_fill, BoardOrientation,
+    BoardTheme, HighlightStyle, ARROW_HEAD, SQUARE_SIZE,
+};
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+use crate::comment_panel::CommentPanel;
+use crate::game_navigator::GameNavigator;
+use crate::halfmove_clock::HalfmoveClock;
+use crate::material_bar::MaterialBar;
+use crate::move_input::{parse_user_move, resolve_san, MoveInput};
+use crate::move_list::{comment_at, state_at_ply};
+use crate::net::{query_param, NetClient, NetMessage};
+use crate::result_banner::ResultBanner;
+use crate::Msg;
+
+#[derive(Properties, PartialEq)]
+struct HighlightProps {
+    x: i32,
+    y: i32,
+    color: String,
+    style: HighlightStyle,
+}
+
+#[derive(Properties, PartialEq)]
+struct SquareProps {
+    x: i32,
+    y: i32,
+    fill: String,
+}
+
+#[function_component(BoardSquare)]
+fn board_square(props: &SquareProps) -> Html {
+    let color = format!("fill:{}", props.fill);
+    let x = format!("{}%", props.x as f32 * SQUARE_SIZE);
+    let y = format!("{}%", props.y as f32 * SQUARE_SIZE);
+    let width = format!("{SQUARE_SIZE}%");
+    let height = format!("{SQUARE_SIZE}%");
+
+    html! {
+        <rect class={"svg"} {x} {y} {width} {height} style={color}/>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct MoveHintProps {
+    x: i32,
+    y: i32,
+    kind: HighlightKind,
+    gives_check: bool,
+    color: String,
+    /// Used for capture rings.
+    style: HighlightStyle,
+}
+
+/// A dot on a square the selected piece can move to, or a ring if the move is a capture.
+#[function_component(MoveHint)]
+fn move_hint(props: &MoveHintProps) -> Html {
+    let cx = format!("{}%", props.x as f32 * 12.5 + 6.25);
+    let cy = format!("{}%", props.y as f32 * 12.5 + 6.25);
+    let color = &props.color;
+    let (r, style) = match props.kind {
+        HighlightKind::Move => ("2%", format!("fill:{color};fill-opacity:0.5")),
+        HighlightKind::Capture => ("5.5%", props.style.css(color)),
+    };
+    // A small "+" in the top-right corner of the square.
+    let check_badge = props.gives_check.then(|| {
+        let center_x = props.x as f32 * 12.5 + 10.5;
+        let center_y = props.y as f32 * 12.5 + 2.0;
+        let style = format!("stroke:{color};stroke-width:2");
+        html! {
+            <>
+            <line class={"svg"} style={style.clone()}
+                x1={format!("{}%", center_x - 1.0)} y1={format!("{center_y}%")}
+                x2={format!("{}%", center_x + 1.0)} y2={format!("{center_y}%")}/>
+            <line class={"svg"} {style}
+                x1={format!("{center_x}%")} y1={format!("{}%", center_y - 1.0)}
+                x2={format!("{center_x}%")} y2={format!("{}%", center_y + 1.0)}/>
+            </>
+        }
+    });
+    html! {
+        <>
+        <circle class={"svg"} {cx} {cy} {r} {style}/>
+        { for check_badge }
+        </>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct ArrowProps {
+    /// Unique among the arrows, names the arrow head marker.
+    id: usize,
+    from: Pos,
+    to: Pos,
+    color: String,
+    bottom: PieceColor,
+}
+
+#[function_component(Arrow)]
+fn arrow(props: &ArrowProps) -> Html {
+    let ((x1, y1), (x2, y2)) = arrow_endpoints(props.from, props.to, props.bottom);
+    let marker = format!("arrow-head-{}", props.id);
+    let style = format!(
+        "stroke:{};stroke-width:2%;stroke-opacity:0.8;marker-end:url(#{marker});pointer-events:none",
+        props.color
+    );
+    // The head is drawn in stroke widths, ARROW_HEAD long.
+    let head_length = ARROW_HEAD / 2.0;
+    html! {
+        <>
+        <defs>
+            <marker id={marker} markerWidth={head_length.to_string()} markerHeight="2"
+                refX="0" refY="1" orient="auto">
+                <polygon points={format!("0 0, {head_length} 1, 0 2")}
+                    style={format!("fill:{};fill-opacity:0.8", props.color)}/>
+            </marker>
+        </defs>
+        <line class={"svg"} {style}
+            x1={format!("{x1}%")} y1={format!("{y1}%")}
+            x2={format!("{x2}%")} y2={format!("{y2}%")}/>
+        </>
+    }
+}
+
+#[function_component(Highlight)]
+fn highlight(props: &HighlightProps) -> Html {
+    let x = format!("{}%", props.x as f32 * 12.5 + 0.5);
+    let y = format!("{}%", props.y as f32 * 12.5 + 0.5);
+    let width = "11.5%";
+    let height = "11.5%";
+    let style = props.style.css(&props.color);
+    html! {
+        <rect class={"svg"} {x} {y} rx={5} ry={5} {width} {height} {style}/>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct PieceProps {
+    pos: Pos,
+    piece: Piece,
+    /// The side drawn at the bottom of the board.
+    bottom: PieceColor,
+}
+
+#[function_component(PieceImage)]
+fn piece_image(props: &PieceProps) -> Html {
+    let (x, y) = oriented_square_coords(props.pos, props.bottom);
+    let x = format!("{}%", x as f32 * SQUARE_SIZE);
+    let y = format!("{}%", y as f32 * SQUARE_SIZE);
+    let handles = use_ref(|| Handles::new());
+    html! {
+        <image class={"svg"} width={"12.5%"} height={"12.5%"} href={handles.get(&props.piece)} {x} {y}/>
+    }
+}
+
+pub struct Board {
+    state: BoardState,
+    /// The position the game started from.
+    start: BoardState,
+    /// The side controlled by the local user, `None` if both sides are played locally.
+    player_color: Option<PieceColor>,
+    premove_from: Option<Pos>,
+    premove: Option<(Pos, Pos)>,
+    net: Option<NetClient>,
+    /// The side which offered a draw that wasn't answered yet.
+    draw_offer: Option<PieceColor>,
+    theme: BoardTheme,
+    highlight_style: HighlightStyle,
+    show_legal_moves: bool,
+    /// Marks the en passant target square, a learning aid.
+    show_en_passant: bool,
+    /// Clicking another own piece switches the selection to it, see [`click_action`].
+    sticky_selection: bool,
+    /// Whether to play a sound for each move, see [`sound_for`].
+    sounds: bool,
+    /// A promotion waiting for the piece to be typed, promoting to a queen by default.
+    pending_promotion: Option<Move>,
+    /// The piece to always promote to instead of asking.
+    auto_promote: Option<PromotedTo>,
+    orientation: BoardOrientation,
+    /// Why the last typed move was rejected.
+    move_input_error: Option<String>,
+    /// The ply shown while stepping through the game, `None` for the live position.
+    viewed_ply: Option<usize>,
+    /// Analysis arrows drawn with the right mouse button.
+    arrows: Vec<(Pos, Pos, String)>,
+    /// Where the arrow being drawn starts.
+    arrow_start: Option<Pos>,
+    /// Whether to label the hovered square, a debug aid for the coordinate mapping.
+    show_coordinates: bool,
+    hovered: Option<Pos>,
+    /// The computer's strength when it plays Black, `None` if it doesn't play.
+    computer: Option<Difficulty>,
+    /// The puzzle being solved, loaded from the `puzzle` and `solution` query parameters.
+    puzzle: Option<Puzzle>,
+    /// How the last attempt at the puzzle went.
+    puzzle_outcome: Option<PuzzleOutcome>,
+    /// The games of the loaded PGN collection.
+    games: Rc<Vec<Result<Game, PgnError>>>,
+    /// The index of the game being replayed.
+    selected_game: Option<usize>,
+    /// Why the last picked game couldn't be loaded.
+    game_load_error: Option<String>,
+}
+
+impl Board {
+    /// The side the local user acts for.
+    fn local_side(&self) -> PieceColor {
+        self.player_color.unwrap_or(self.state.side_to_move())
+    }
+
+    /// The side drawn at the bottom of the board right now.
+    fn bottom(&self) -> PieceColor {
+        self.orientation.bottom(self.state.side_to_move())
+    }
+
+    fn square_coords(&self, pos: Pos) -> (i32, i32) {
+        oriented_square_coords(pos, self.bottom())
+    }
+
+    fn send(&self, msg: NetMessage) {
+        if let Some(net) = &self.net {
+            net.send(&msg);
+        }
+    }
+
+    fn is_opponents_turn(&self) -> bool {
+        self.player_color
+            .map_or(false, |color| color != self.state.side_to_move())
+    }
+
+    /// Moves can be taken back in local games only, and not after the game ended.
+    fn can_take_back(&self, moves_played: usize) -> bool {
+        moves_played > 0
+            && self.state.game_result.is_none()
+            && self.net.is_none()
+            && self.puzzle.is_none()
+    }
+
+    fn click_premove(&mut self, pos: Pos) {
+        if let Some(from) = self.premove_from.take() {
+            self.premove = Some((from, pos));
+        } else {
+            self.premove = None;
+            if let Square::Piece(piece) = self.state.square_by_pos(pos) {
+                if Some(piece.color) == self.player_color {
+                    self.premove_from = Some(pos);
+                }
+            }
+        }
+    }
+
+    fn try_premove(&mut self, ctx: &Context<Self>) {
+        if let Some((from, to)) = self.premove.take() {
+            if let Some(mv) = self.state.legal_move_between(from, to) {
+                self.make_move(ctx, mv);
+            }
+        }
+    }
+
+    fn make_move(&mut self, ctx: &Context<Self>, mv: Move) {
+        let mut puzzle_reply = None;
+        let side = self.state.side_to_move();
+        let puzzle = self.puzzle.as_mut();
+        if let Some(puzzle) = puzzle.filter(|p| p.solver() == side && !p.is_solved()) {
+            let outcome = submit_move(puzzle, mv);
+            self.puzzle_outcome = Some(outcome);
+            match outcome {
+                PuzzleOutcome::Correct { reply } => puzzle_reply = Some(reply),
+                PuzzleOutcome::Incorrect => return,
+                PuzzleOutcome::Solved => {}
+            }
+        }
+        let is_local_move = !self.is_opponents_turn();
+        let uci = self.state.to_uci(mv);
+        let san_move = self.state.to_san_move(mv);
+        // check roundtrip
+        debug_assert_eq!(self.state.from_san_move(san_move), Ok(mv));
+        let event = self.state.make_move(mv);
+        self.viewed_ply = None;
+        if self.sounds {
+            play_sound(event);
+        }
+        ctx.props().move_list.push(san_move);
+        if let Some(net) = self.net.as_ref().filter(|_| is_local_move) {
+            net.send(&NetMessage::MakeMove(uci));
+        }
+        self.state.game_result = self.state.outcome();
+        if self.player_color == Some(self.state.side_to_move()) {
+            self.try_premove(ctx);
+        } else if self.state.game_result.is_none() {
+            self.play_computer_move(ctx);
+        }
+        if let Some(reply) = puzzle_reply {
+            self.make_move(ctx, reply);
+        }
+    }
+
+    /// Sets up a game of a PGN collection for replay from its first move. Nothing changes
+    /// if the game can't be replayed.
+    fn load_game(&mut self, ctx: &Context<Self>, game: &Game) -> Result<(), PgnError> {
+        let move_list = &ctx.props().move_list;
+        let start = start_position(&game.tags)?;
+        self.state = replay(&game.tags, &game.moves)?;
+        self.start = start;
+        self.computer = None;
+        self.player_color = None;
+        self.puzzle = None;
+        self.puzzle_outcome = None;
+        self.pending_promotion = None;
+        self.premove_from = None;
+        self.premove = None;
+        self.draw_offer = None;
+        self.move_input_error = None;
+        move_list.clear();
+        move_list.set_start(&self.start);
+        for &san in &game.moves {
+            move_list.push(san);
+        }
+        self.state.game_result = self.state.outcome();
+        self.viewed_ply = (move_list.len() > 0).then_some(0);
+        Ok(())
+    }
+
+    fn play_computer_move(&mut self, ctx: &Context<Self>) {
+        let is_computers_turn =
+            self.puzzle.is_none() && self.state.side_to_move() == PieceColor::Black;
+        if let Some(difficulty) = self.computer.filter(|_| is_computers_turn) {
+            let seed = (js_sys::Math::random() * u32::MAX as f64) as u64;
+            if let Some(mv) = computer_move(&self.state, difficulty, seed) {
+                self.make_move(ctx, mv);
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Properties)]
+pub struct BoardProps {
+    pub move_list: crate::Moves,
+    #[prop_or_default]
+    pub theme: BoardTheme,
+}
+
+impl Component for Board {
+    type Message = Msg;
+    type Properties = BoardProps;
+    fn create(ctx: &Context<Self>) -> Self {
+        let search = web_sys::window()
+            .and_then(|w| w.location().search().ok())
+            .unwrap_or_default();
+        let net = match (query_param(&search, "relay"), query_param(&search, "game")) {
+            (Some(relay), Some(game_id)) => {
+                let on_message = ctx.link().callback(Msg::Net);
+                NetClient::connect(&relay, game_id, on_message)
+                    .map_err(|_| gloo::console::error!("Failed to connect to relay"))
+                    .ok()
+            }
+            _ => None,
+        };
+        let puzzle = query_param(&search, "puzzle")
+            .zip(query_param(&search, "solution"))
+            .and_then(|(fen, solution)| {
+                Puzzle::from_uci(&decode_query_value(&fen), &decode_query_value(&solution))
+            });
+        let state = puzzle
+            .as_ref()
+            .map_or_else(BoardState::new, |puzzle| puzzle.state().clone());
+        ctx.props().move_list.set_start(&state);
+        Self {
+            start: state.clone(),
+            state,
+            player_color: None,
+            premove_from: None,
+            premove: None,
+            net,
+            draw_offer: None,
+            theme: ctx.props().theme.clone(),
+            highlight_style: HighlightStyle::default(),
+            show_legal_moves: true,
+            show_en_passant: false,
+            sticky_selection: false,
+            sounds: false,
+            pending_promotion: None,
+            auto_promote: None,
+            orientation: BoardOrientation::default(),
+            move_input_error: None,
+            viewed_ply: None,
+            arrows: Vec::new(),
+            arrow_start: None,
+            show_coordinates: false,
+            hovered: None,
+            computer: None,
+            puzzle,
+            puzzle_outcome: None,
+            games: Rc::default(),
+            selected_game: None,
+            game_load_error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::ClickOnSquare(pos) => {
+                gloo::console::log!("Click on square: {}", pos.to_string());
+                self.arrows.clear();
+                if self.viewed_ply.take().is_some() {
+                    return true;
+                }
+                if self.state.game_result.is_some() {
+                    return false;
+                }
+                if let Some(mv) = self.pending_promotion.take() {
+                    self.make_move(ctx, mv);
+                    return true;
+                }
+                if self.is_opponents_turn() {
+                    self.click_premove(pos);
+                    return true;
+                }
+                self.state.stop_highlighting();
+                let selected = self.state.selected_piece.take().map(|(_, from)| from);
+                match click_action(&self.state, selected, pos, self.sticky_selection) {
+                    ClickAction::Select(piece) => {
+                        self.state.selected_piece = Some((piece, pos));
+                        if self.show_legal_moves {
+                            self.state.hightlight_legal_moves(piece, pos);
+                        }
+                    }
+                    ClickAction::Move(mv) => match move_to_play(self.auto_promote, mv) {
+                        Some(mv) => self.make_move(ctx, mv),
+                        None => self.pending_promotion = Some(mv),
+                    },
+                    ClickAction::Deselect => {}
+                }
+                true
+            }
+            Msg::KeyPressed(key) => {
+                let promotion = self.pending_promotion.zip(promotion_from_key(&key));
+                if let Some((mv, promoted)) = promotion {
+                    self.pending_promotion = None;
+                    self.make_move(ctx, mv.with_promotion(promoted));
+                    return true;
+                }
+                false
+            }
+            Msg::SubmitMove(input) => {
+                if self.state.game_result.is_some() || self.is_opponents_turn() {
+                    self.move_input_error = Some("You can't move now".to_string());
+                    return true;
+                }
+                match parse_user_move(&input, &self.state) {
+                    Ok(mv) => {
+                        self.move_input_error = None;
+                        self.pending_promotion = None;
+                        self.state.selected_piece = None;
+                        self.state.stop_highlighting();
+                        self.make_move(ctx, mv);
+                    }
+                    Err(err) => self.move_input_error = Some(err.to_string()),
+                }
+                true
+            }
+            #[cfg(debug_assertions)]
+            Msg::DebugSan(input) => {
+                match resolve_san(&input, &self.state) {
+                    Ok(mv) => {
+                        self.make_move(ctx, mv);
+                        gloo::console::log!("FEN:", self.state.to_fen());
+                    }
+                    Err(err) => gloo::console::error!(err.to_string()),
+                }
+                true
+            }
+            #[cfg(debug_assertions)]
+            Msg::SetShowCoordinates(show) => {
+                self.show_coordinates = show;
+                self.hovered = None;
+                true
+            }
+            Msg::HoverSquare(pos) => {
+                let changed = self.hovered != pos;
+                self.hovered = pos;
+                changed
+            }
+            Msg::GoToPly(ply) => {
+                self.state.selected_piece = None;
+                self.state.stop_highlighting();
+                self.viewed_ply = (ply < ctx.props().move_list.len()).then_some(ply);
+                true
+            }
+            Msg::LoadGames(text) => {
+                self.games = Rc::new(from_pgn_collection(&text));
+                self.selected_game = None;
+                self.game_load_error = None;
+                true
+            }
+            Msg::SelectGame(index) => {
+                let games = Rc::clone(&self.games);
+                match games.get(index) {
+                    Some(Ok(game)) => {
+                        match self.load_game(ctx, game) {
+                            Ok(()) => {
+                                self.selected_game = Some(index);
+                                self.game_load_error = None;
+                            }
+                            Err(err) => self.game_load_error = Some(err.to_string()),
+                        }
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Msg::EditComment(comment) => {
+                let move_list = &ctx.props().move_list;
+                let ply = self.viewed_ply.unwrap_or_else(|| move_list.len());
+                move_list.set_comment(ply, comment);
+                true
+            }
+            Msg::RequestNewGame => {
+                let moves_played = ctx.props().move_list.len();
+                if should_confirm_new_game(moves_played, self.state.game_result)
+                    && !gloo::dialogs::confirm("Abandon the game in progress?")
+                {
+                    return false;
+                }
+                self.update(ctx, Msg::NewGame)
+            }
+            Msg::NewGame => {
+                self.state = BoardState::new();
+                self.start = BoardState::new();
+                self.puzzle = None;
+                self.puzzle_outcome = None;
+                ctx.props().move_list.set_start(&self.start);
+                self.pending_promotion = None;
+                self.premove_from = None;
+                self.premove = None;
+                self.draw_offer = None;
+                self.move_input_error = None;
+                self.viewed_ply = None;
+                ctx.props().move_list.clear();
+                true
+            }
+            Msg::Resign => {
+                let resigned_side = self.local_side();
+                self.state.game_result = Some(GameResult::Resignation { resigned_side });
+                self.send(NetMessage::Resign);
+                true
+            }
+            Msg::OfferDraw => {
+                self.draw_offer = Some(self.local_side());
+                self.send(NetMessage::OfferDraw);
+                true
+            }
+            Msg::AcceptDraw => {
+                if self.draw_offer.take().is_some() {
+                    self.state.game_result = Some(GameResult::DrawByAgreement);
+                    self.send(NetMessage::AcceptDraw);
+                }
+                true
+            }
+            Msg::DeclineDraw => {
+                if self.draw_offer.take().is_some() {
+                    self.send(NetMessage::DeclineDraw);
+                }
+                true
+            }
+            Msg::ClaimFiftyMoveDraw => {
+                if self.state.is_fifty_move_draw() {
+                    self.state.game_result = Some(GameResult::DrawByFiftyMoveRule);
+                    self.send(NetMessage::ClaimFiftyMoveDraw);
+                }
+                true
+            }
+            Msg::TakeBack => {
+                let move_list = &ctx.props().move_list;
+                if !self.can_take_back(move_list.len()) {
+                    return false;
+                }
+                // Against the computer, its reply is taken back too.
+                let plies = match self.computer {
+                    Some(_) if move_list.len() > 1 => 2,
+                    _ => 1,
+                };
+                for _ in 0..plies {
+                    self.state.unmake_move();
+                    move_list.pop();
+                }
+                self.pending_promotion = None;
+                self.premove_from = None;
+                self.premove = None;
+                self.viewed_ply = None;
+                true
+            }
+            Msg::SetBoardTheme(theme) => {
+                self.theme = theme;
+                true
+            }
+            Msg::SetHighlightStyle(style) => {
+                self.highlight_style = style;
+                true
+            }
+            Msg::StartArrow(pos) => {
+                self.arrow_start = Some(pos);
+                true
+            }
+            Msg::AddArrow(from, to, color) => {
+                self.arrow_start = None;
+                // Drawing the same arrow again removes it.
+                let len = self.arrows.len();
+                self.arrows
+                    .retain(|(f, t, c)| (*f, *t, c.as_str()) != (from, to, color.as_str()));
+                if self.arrows.len() == len && from != to {
+                    self.arrows.push((from, to, color));
+                }
+                true
+            }
+            Msg::ClearArrows => {
+                self.arrows.clear();
+                true
+            }
+            Msg::SetAutoPromote(auto_promote) => {
+                self.auto_promote = auto_promote;
+                true
+            }
+            Msg::SetDifficulty(difficulty) => {
+                // Network games are played between people.
+                if self.net.is_some() {
+                    return false;
+                }
+                self.computer = difficulty;
+                self.player_color = difficulty.map(|_| PieceColor::White);
+                if self.state.game_result.is_none() {
+                    self.play_computer_move(ctx);
+                }
+                true
+            }
+            Msg::SetShowEnPassant(show) => {
+                self.show_en_passant = show;
+                true
+            }
+            Msg::SetStickySelection(sticky) => {
+                self.sticky_selection = sticky;
+                true
+            }
+            Msg::SetSounds(sounds) => {
+                self.sounds = sounds;
+                true
+            }
+            Msg::SetShowLegalMoves(show) => {
+                self.show_legal_moves = show;
+                if let Some((piece, pos)) = self.state.selected_piece.filter(|_| show) {
+                    self.state.hightlight_legal_moves(piece, pos);
+                } else {
+                    self.state.stop_highlighting();
+                }
+                true
+            }
+            Msg::SetOrientation(orientation) => {
+                self.orientation = orientation;
+                true
+            }
+            Msg::Net(msg) => {
+                match msg {
+                    NetMessage::AssignColor { white } => {
+                        let color = if white {
+                            PieceColor::White
+                        } else {
+                            PieceColor::Black
+                        };
+                        self.player_color = Some(color);
+                    }
+                    NetMessage::MakeMove(uci) => {
+                        let mv = self
+                            .state
+                            .from_uci(&uci)
+                            .filter(|_| self.is_opponents_turn());
+                        match mv {
+                            Some(mv) => self.make_move(ctx, mv),
+                            None => gloo::console::error!("Rejected move from opponent:", uci),
+                        }
+                    }
+                    NetMessage::Resign => {
+                        let resigned_side = !self.local_side();
+                        self.state.game_result = Some(GameResult::Resignation { resigned_side });
+                    }
+                    NetMessage::OfferDraw => self.draw_offer = Some(!self.local_side()),
+                    NetMessage::AcceptDraw => {
+                        if self.draw_offer.take() == Some(self.local_side()) {
+                            self.state.game_result = Some(GameResult::DrawByAgreement);
+                        }
+                    }
+                    NetMessage::DeclineDraw => self.draw_offer = None,
+                    NetMessage::ClaimFiftyMoveDraw => {
+                        if self.state.is_fifty_move_draw() {
+                            self.state.game_result = Some(GameResult::DrawByFiftyMoveRule);
+                        }
+                    }
+                    other => gloo::console::log!("Unhandled message:", format!("{other:?}")),
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let row = move |y| {
+            (0..8).map(move |x| {
+                let fill = square_fill(&self.theme, square_color(x, y)).to_string();
+                html! { <BoardSquare {fill} {x} {y}/> }
+            })
+        };
+        let squares = (0..8).map(|y| row(y)).flatten();
+        let bottom = self.bottom();
+        let move_list = &ctx.props().move_list;
+        let viewed_state = self
+            .viewed_ply
+            .map(|ply| state_at_ply(&self.start, &move_list.inner.borrow(), ply));
+        let displayed_state = viewed_state.as_ref().unwrap_or(&self.state);
+        let balance = displayed_state.material_balance();
+        let pieces = displayed_state.pieces().map(|(piece, pos)| {
+            html! {
+                <PieceImage {pos} {piece} {bottom}/>
+            }
+        });
+        let hints: &[_] = if should_show_move_hints(self.show_legal_moves, self.viewed_ply) {
+            self.state.highlights()
+        } else {
+            &[]
+        };
+        let move_hints = hints.iter().map(|target| {
+            let (x, y) = self.square_coords(target.to);
+            let kind = target.kind;
+            let gives_check = target.gives_check;
+            let color = self.theme.hint.clone();
+            let style = self.highlight_style;
+            html! {
+                <MoveHint {x} {y} {kind} {gives_check} {color} {style}/>
+            }
+        });
+        let en_passant_marker = displayed_state
+            .en_passant()
+            .filter(|_| self.show_en_passant)
+            .map(|pos| {
+                let (x, y) = self.square_coords(pos);
+                let cx = format!("{}%", x as f32 * SQUARE_SIZE + SQUARE_SIZE / 2.0);
+                let cy = format!("{}%", y as f32 * SQUARE_SIZE + SQUARE_SIZE / 2.0);
+                let style = format!(
+                    "fill-opacity:0;stroke:{};stroke-width:0.5%;stroke-opacity:0.4;stroke-dasharray:1%",
+                    self.theme.hint
+                );
+                html! {
+                    <circle class={"svg"} {cx} {cy} r="4%" {style}/>
+                }
+            });
+        let onclick = ctx
+            .link()
+            .callback(move |event: MouseEvent| Msg::ClickOnSquare(event_pos(&event, bottom)));
+        let onmousedown = ctx.link().batch_callback(move |event: MouseEvent| {
+            (event.button() == 2).then(|| Msg::StartArrow(event_pos(&event, bottom)))
+        });
+        let arrow_start = self.arrow_start;
+        let onmouseup = ctx.link().batch_callback(move |event: MouseEvent| {
+            let color = if event.shift_key() {
+                ALT_ARROW_COLOR
+            } else {
+                ARROW_COLOR
+            };
+            arrow_start
+                .filter(|_| event.button() == 2)
+                .map(|from| Msg::AddArrow(from, event_pos(&event, bottom), color.to_string()))
+        });
+        let oncontextmenu = Callback::from(|event: MouseEvent| event.prevent_default());
+        let show_coordinates = self.show_coordinates;
+        let onmousemove = ctx.link().batch_callback(move |event: MouseEvent| {
+            show_coordinates.then(|| Msg::HoverSquare(Some(event_pos(&event, bottom))))
+        });
+        let onmouseleave = ctx
+            .link()
+            .batch_callback(move |_: MouseEvent| show_coordinates.then(|| Msg::HoverSquare(None)));
+        let coordinate_label = self.hovered.filter(|_| show_coordinates).map(|pos| {
+            let (x, y) = self.square_coords(pos);
+            let x = format!("{}%", x as f32 * SQUARE_SIZE + 0.5);
+            let y = format!("{}%", (y + 1) as f32 * SQUARE_SIZE - 0.5);
+            html! {
+                <text class={"svg"} {x} {y} style="font-size:1.5em;pointer-events:none">
+                    { pos.to_string() }
+                </text>
+            }
+        });
+        let arrows = self
+            .arrows
+            .iter()
+            .enumerate()
+            .map(|(id, (from, to, color))| {
+                let (from, to, color) = (*from, *to, color.clone());
+                html! {
+                    <Arrow {id} {from} {to} {color} {bottom}/>
+                }
+            });
+        let clear_arrows = (!self.arrows.is_empty()).then(|| {
+            let onclick = ctx.link().callback(|_| Msg::ClearArrows);
+            html! {
+                <button {onclick}>{ "Clear arrows" }</button>
+            }
+        });
+        let active_piece_highlight = self.state.selected_piece.map(|(piece, pos)| {
+            let (x, y) = self.square_coords(pos);
+            let color = self.theme.selection.clone();
+            let style = self.highlight_style;
+            html! {
+                <Highlight {x} {y} {color} {style} />
+            }
+        });
+        let turn_indicator = turn_indicator(displayed_state).map(|text| {
+            html! {
+                <div class={classes!("p-2")}>{ text }</div>
+            }
+        });
+        let puzzle_status = self.puzzle.as_ref().map(|puzzle| {
+            let text = puzzle_message(puzzle.solver(), self.puzzle_outcome);
+            html! {
+                <div class={classes!("p-2")}>{ text }</div>
+            }
+        });
+        let result_banner = self.state.game_result.map(|result| {
+            let on_new_game = ctx.link().callback(|_| Msg::NewGame);
+            html! {
+                <ResultBanner {result} {on_new_game}/>
+            }
+        });
+        let premove_highlight = self
+            .premove_from
+            .into_iter()
+            .chain(self.premove.into_iter().flat_map(|(from, to)| [from, to]))
+            .map(|pos| {
+                let (x, y) = self.square_coords(pos);
+                let color = self.theme.premove.clone();
+                let style = self.highlight_style;
+                html! {
+                    <Highlight {x} {y} {color} {style} />
+                }
+            });
+        let game_controls = self.state.game_result.is_none().then(|| {
+            let on_resign = ctx.link().callback(|_| Msg::Resign);
+            let on_offer_draw = ctx.link().callback(|_| Msg::OfferDraw);
+            let on_claim = ctx.link().callback(|_| Msg::ClaimFiftyMoveDraw);
+            let plies = self.state.plies_since_last_non_repeatable_move();
+            let perpetual_check = self
+                .state
+                .is_perpetual_check_likely()
+                .then(|| html! { <span>{ "Perpetual check, offer a draw?" }</span> });
+            let back_rank_hints = [PieceColor::White, PieceColor::Black]
+                .into_iter()
+                .filter(|color| self.state.back_rank_weakness(*color))
+                .map(|color| html! { <span>{ format!("{color}'s back rank is weak") }</span> });
+            let on_take_back = ctx.link().callback(|_| Msg::TakeBack);
+            let take_back_disabled = !self.can_take_back(move_list.len());
+            html! {
+                <div class={classes!("flex", "flex-row", "items-center", "gap-4", "p-2")}>
+                    <button onclick={on_take_back} disabled={take_back_disabled}>
+                        { "Take back" }
+                    </button>
+                    <button onclick={on_resign}>{ "Resign" }</button>
+                    <button onclick={on_offer_draw}>{ "Offer draw" }</button>
+                    <HalfmoveClock {plies} {on_claim}/>
+                    { for perpetual_check }
+                    { for back_rank_hints }
+                </div>
+            }
+        });
+        // In a local game both sides share the screen, so the offering side answers too.
+        let draw_prompt = self
+            .draw_offer
+            .filter(|side| self.player_color != Some(*side))
+            .map(|side| {
+                let on_accept = ctx.link().callback(|_| Msg::AcceptDraw);
+                let on_decline = ctx.link().callback(|_| Msg::DeclineDraw);
+                html! {
+                    <div class={classes!("flex", "flex-row", "items-center", "gap-4", "p-2")}>
+                        <span>{ format!("{side} offers a draw") }</span>
+                        <button onclick={on_accept}>{ "Accept" }</button>
+                        <button onclick={on_decline}>{ "Decline" }</button>
+                    </div>
+                }
+            });
+        let theme_buttons = BoardTheme::presets().into_iter().map(|(name, theme)| {
+            let onclick = ctx
+                .link()
+                .callback(move |_| Msg::SetBoardTheme(theme.clone()));
+            html! {
+                <button {onclick}>{ name }</button>
+            }
+        });
+        let highlight_buttons = HighlightStyle::presets().into_iter().map(|(name, style)| {
+            let onclick = ctx.link().callback(move |_| Msg::SetHighlightStyle(style));
+            html! {
+                <button {onclick}>{ name }</button>
+            }
+        });
+        let show_legal_moves = !self.show_legal_moves;
+        let legal_moves_button = {
+            let onclick = ctx
+                .link()
+                .callback(move |_| Msg::SetShowLegalMoves(show_legal_moves));
+            let label = if self.show_legal_moves {
+                "Hide legal moves"
+            } else {
+                "Show legal moves"
+            };
+            html! {
+                <button {onclick}>{ label }</button>
+            }
+        };
+        let en_passant_button = {
+            let show = !self.show_en_passant;
+            let onclick = ctx.link().callback(move |_| Msg::SetShowEnPassant(show));
+            let label = if self.show_en_passant {
+                "Hide en passant"
+            } else {
+                "Show en passant"
+            };
+            html! {
+                <button {onclick}>{ label }</button>
+            }
+        };
+        let sticky_selection_button = {
+            let sticky = !self.sticky_selection;
+            let onclick = ctx
+                .link()
+                .callback(move |_| Msg::SetStickySelection(sticky));
+            let label = if self.sticky_selection {
+                "Sticky selection: on"
+            } else {
+                "Sticky selection: off"
+            };
+            html! {
+                <button {onclick}>{ label }</button>
+            }
+        };
+        let sounds_button = {
+            let sounds = !self.sounds;
+            let onclick = ctx.link().callback(move |_| Msg::SetSounds(sounds));
+            let label = if self.sounds {
+                "Sounds: on"
+            } else {
+                "Sounds: off"
+            };
+            html! {
+                <button {onclick}>{ label }</button>
+            }
+        };
+        let difficulty_select = self.net.is_none().then(|| {
+            let onchange = ctx.link().callback(|event: Event| {
+                let name = event.target_unchecked_into::<HtmlSelectElement>().value();
+                Msg::SetDifficulty(Difficulty::ALL.into_iter().find(|d| d.name() == name))
+            });
+            let options = Difficulty::ALL.into_iter().map(|difficulty| {
+                let selected = self.computer == Some(difficulty);
+                html! {
+                    <option value={difficulty.name()} {selected}>
+                        { format!("Computer: {}", difficulty.name()) }
+                    </option>
+                }
+            });
+            html! {
+                <select {onchange}>
+                    <option value="" selected={self.computer.is_none()}>{ "Two players" }</option>
+                    { for options }
+                </select>
+            }
+        });
+        let auto_promote_select = {
+            let onchange = ctx.link().callback(|event: Event| {
+                let key = event.target_unchecked_into::<HtmlSelectElement>().value();
+                Msg::SetAutoPromote(promotion_from_key(&key))
+            });
+            let options = [
+                ("", "Ask"),
+                ("Q", "Queen"),
+                ("R", "Rook"),
+                ("B", "Bishop"),
+                ("N", "Knight"),
+            ]
+            .into_iter()
+            .map(|(key, name)| {
+                let selected = self.auto_promote == promotion_from_key(key);
+                html! {
+                    <option value={key} {selected}>{ format!("Promote: {name}") }</option>
+                }
+            });
+            html! {
+                <select {onchange}>
+                    { for options }
+                </select>
+            }
+        };
+        let orientation_buttons = [
+            ("White at bottom", BoardOrientation::White),
+            ("Black at bottom", BoardOrientation::Black),
+            ("Side to move", BoardOrientation::SideToMove),
+        ]
+        .into_iter()
+        .map(|(name, orientation)| {
+            let onclick = ctx
+                .link()
+                .callback(move |_| Msg::SetOrientation(orientation));
+            html! {
+                <button {onclick}>{ name }</button>
+            }
+        });
+        let promotion_prompt = self.pending_promotion.map(|_| {
+            html! {
+                <div class={classes!("p-2")}>
+                    { "Promote to: press Q, R, B or N (click anywhere for a queen)" }
+                </div>
+            }
+        });
+        let move_input = self.state.game_result.is_none().then(|| {
+            let on_submit = ctx.link().callback(Msg::SubmitMove);
+            let error = self.move_input_error.clone();
+            html! {
+                <MoveInput {error} {on_submit}/>
+            }
+        });
+        #[cfg(debug_assertions)]
+        let debug_console = {
+            let on_submit = ctx.link().callback(Msg::DebugSan);
+            html! {
+                <div class={classes!("flex", "flex-row", "items-center", "p-2")}>
+                    <span>{ "Debug SAN:" }</span>
+                    <MoveInput error={None} {on_submit}/>
+                </div>
+            }
+        };
+        #[cfg(not(debug_assertions))]
+        let debug_console = html! {};
+        #[cfg(debug_assertions)]
+        let coordinates_button = {
+            let show = !self.show_coordinates;
+            let onclick = ctx.link().callback(move |_| Msg::SetShowCoordinates(show));
+            html! {
+                <button {onclick}>{ "Debug: coordinates" }</button>
+            }
+        };
+        #[cfg(not(debug_assertions))]
+        let coordinates_button = html! {};
+        let last_ply = move_list.len();
+        let ply = self.viewed_ply.unwrap_or(last_ply);
+        let go_to = |target: usize| ctx.link().callback(move |_| Msg::GoToPly(target));
+        let replay_controls = html! {
+            <div class={classes!("flex", "flex-row", "items-center", "gap-2", "p-2")}>
+                <button onclick={go_to(0)}>{ "|<" }</button>
+                <button onclick={go_to(ply.saturating_sub(1))}>{ "<" }</button>
+                <button onclick={go_to(ply + 1)}>{ ">" }</button>
+                <button onclick={go_to(last_ply)}>{ ">|" }</button>
+            </div>
+        };
+        let comment = comment_at(&move_list.inner.borrow(), ply).map(ToString::to_string);
+        let on_edit = ctx.link().callback(Msg::EditComment);
+        let onkeydown = ctx.link().callback(|event: KeyboardEvent| {
+            if event.alt_key() && event.key().eq_ignore_ascii_case("n") {
+                Msg::RequestNewGame
+            } else {
+                Msg::KeyPressed(event.key())
+            }
+        });
+        let on_new_game = ctx.link().callback(|_| Msg::RequestNewGame);
+        let on_load_games = ctx.link().callback(Msg::LoadGames);
+        let on_select_game = ctx.link().callback(Msg::SelectGame);
+        let games = Rc::clone(&self.games);
+        let load_error = self.game_load_error.clone();
+        html! {
+            <div {onkeydown} tabindex="0" class={classes!("h-full", "flex", "flex-col")}>
+                <div class={classes!("flex", "flex-row", "gap-4", "p-2")}>
+                    <button onclick={on_new_game} title="Alt+N">{ "New game" }</button>
+                    { for theme_buttons }
+                    { for highlight_buttons }
+                    { legal_moves_button }
+                    { en_passant_button }
+                    { sticky_selection_button }
+                    { sounds_button }
+                    { for difficulty_select }
+                    { auto_promote_select }
+                    { for clear_arrows }
+                    { for orientation_buttons }
+                    { coordinates_button }
+                </div>
+                { for turn_indicator }
+                { for puzzle_status }
+                { for result_banner }
+                { for game_controls }
+                { for draw_prompt }
+                { for promotion_prompt }
+                { for move_input }
+                { debug_console }
+                { replay_controls }
+                <MaterialBar {balance}/>
+                <CommentPanel {ply} {comment} {on_edit}/>
+                <GameNavigator {games} selected={self.selected_game} error={load_error}
+                    on_load={on_load_games} on_select={on_select_game}/>
+                <svg {onclick} {onmousedown} {onmouseup} {oncontextmenu}
+                    {onmousemove} {onmouseleave}
+                    class={classes!("h-full", "aspect-square")}>
+                    { for squares }
+                    { for pieces }
+                    { for move_hints }
+                    { for en_passant_marker }
+                    { for active_piece_highlight }
+                    { for premove_highlight }
+                    { for arrows }
+                    { for coordinate_label }
+                </svg>
+            </div>
+        }
+    }
+}
+
+/// The sound file played for a move. The files aren't bundled, so sounds are off until
+/// they are put under `resources/sounds`.
+fn sound_for(event: MoveEvent) -> &'static str {
+    match event {
+        MoveEvent::Move => "./resources/sounds/move.mp3",
+        MoveEvent::Capture => "./resources/sounds/capture.mp3",
+        MoveEvent::Castle => "./resources/sounds/castle.mp3",
+        MoveEvent::Check => "./resources/sounds/check.mp3",
+        MoveEvent::Promotion => "./resources/sounds/promotion.mp3",
+        MoveEvent::GameEnd => "./resources/sounds/game_end.mp3",
+    }
+}
+
+fn play_sound(event: MoveEvent) {
+    if let Ok(audio) = web_sys::HtmlAudioElement::new_with_src(sound_for(event)) {
+        // Playback may be rejected by the browser (e.g. autoplay policy), it's fine to ignore.
+        let _ = audio.play();
+    }
+}
+
+/// Arrow colors, the second one is used with Shift held.
+const ARROW_COLOR: &str = "#15781b";
+const ALT_ARROW_COLOR: &str = "#882020";
+
+/// The square under the mouse pointer.
+fn event_pos(event: &MouseEvent, bottom: PieceColor) -> Pos {
+    let svg: web_sys::Element = event.target_dyn_into().unwrap();
+    let rect = svg.get_bounding_client_rect();
+    let x = event.offset_x() as f32 / rect.width() as f32;
+    let y = event.offset_y() as f32 / rect.height() as f32;
+    cursor_position_to_pos((x, y), bottom)
+}
+
+pub fn cursor_position_to_pos((x, y): (f32, f32), bottom: PieceColor) -> Pos {
+    let column = (x * 8.0).ceil() as u8;
+    let row = (y * 8.0).ceil() as u8;
+    let (file, rank) = match bottom {
+        PieceColor::White => (column, 9 - row),
+        PieceColor::Black => (9 - column, row),
+    };
+    Pos::new(File::from_u8(file), Rank::new(rank))
+}
+
+/// Query values can't hold spaces, which FEN records are full of.
+fn decode_query_value(value: &str) -> String {
+    let value = value.replace('+', " ");
+    js_sys::decode_uri_component(&value).map_or(value, String::from)
+}
+
+fn puzzle_message(solver: PieceColor, outcome: Option<PuzzleOutcome>) -> String {
+    match outcome {
+        None => format!("Puzzle: find the best move for {solver}"),
+        Some(PuzzleOutcome::Correct { .. }) => "Correct, keep going".to_string(),
+        Some(PuzzleOutcome::Incorrect) => "Incorrect, try again".to_string(),
+        Some(PuzzleOutcome::Solved) => "Solved!".to_string(),
+    }
+}
+
+/// The move number and side to move like "Move 12, Black to move", nothing once the game
+/// is over.
+fn turn_indicator(state: &BoardState) -> Option<String> {
+    state
+        .game_result
+        .is_none()
+        .then(|| format!("Move {}, {} to move", state.move_number(), state.turn()))
+}
+
+/// Legal move hints belong to the live position, so they're hidden while stepping
+/// through the game as well as when the player turned them off.
+fn should_show_move_hints(show_legal_moves: bool, viewed_ply: Option<usize>) -> bool {
+    show_legal_moves && viewed_ply.is_none()
+}
+
+/// Starting over loses the game in progress, so it's confirmed first. Finished and
+/// untouched games are reset right away.
+fn should_confirm_new_game(moves_played: usize, game_result: Option<GameResult>) -> bool {
+    moves_played > 0 && game_result.is_none()
+}
+
+/// What a click on a square does while the side to move may move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClickAction {
+    /// Selects the piece on the clicked square.
+    Select(Piece),
+    /// Plays the selected piece's move to the clicked square.
+    Move(Move),
+    /// Leaves nothing selected.
+    Deselect,
+}
+
+/// Without `sticky`, any click with a piece selected either moves it or drops the
+/// selection. With it, clicking another piece of the side to move selects that one.
+fn click_action(state: &BoardState, selected: Option<Pos>, pos: Pos, sticky: bool) -> ClickAction {
+    let own_piece = match state.square_by_pos(pos) {
+        Square::Piece(piece) if piece.color == state.side_to_move() => Some(piece),
+        _ => None,
+    };
+    match (selected, own_piece) {
+        (Some(from), _) => match state.legal_move_between(from, pos) {
+            Some(mv) => ClickAction::Move(mv),
+            None => match own_piece {
+                Some(piece) if sticky && from != pos => ClickAction::Select(piece),
+                _ => ClickAction::Deselect,
+            },
+        },
+        (None, Some(piece)) => ClickAction::Select(piece),
+        (None, None) => ClickAction::Deselect,
+    }
+}
+
+/// The move to play right away, or `None` if it's a promotion waiting for the player to
+/// pick the piece.
+fn move_to_play(auto_promote: Option<PromotedTo>, mv: Move) -> Option<Move> {
+    match (mv.promoted(), auto_promote) {
+        (None, _) => Some(mv),
+        (Some(_), Some(piece)) => Some(mv.with_promotion(piece)),
+        (Some(_), None) => None,
+    }
+}
+
+/// Maps the letters typed while a promotion is pending to the piece.
+fn promotion_from_key(key: &str) -> Option<PromotedTo> {
+    match key {
+        "q" | "Q" => Some(PromotedTo::Queen),
+        "r" | "R" => Some(PromotedTo::Rook),
+        "b" | "B" => Some(PromotedTo::Bishop),
+        "n" | "N" => Some(PromotedTo::Knight),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn promotion_keys() {
+        assert_eq!(promotion_from_key("q"), Some(PromotedTo::Queen));
+        assert_eq!(promotion_from_key("R"), Some(PromotedTo::Rook));
+        assert_eq!(promotion_from_key("b"), Some(PromotedTo::Bishop));
+        assert_eq!(promotion_from_key("n"), Some(PromotedTo::Knight));
+        assert_eq!(promotion_from_key("k"), None);
+        assert_eq!(promotion_from_key("Enter"), None);
+        assert_eq!(promotion_from_key(""), None);
+    }
+
+    #[test]
+    fn side_to_move_text() {
+        let mut state = BoardState::new();
+        assert_eq!(
+            turn_indicator(&state).as_deref(),
+            Some("Move 1, White to move")
+        );
+        state.make_move(state.from_uci("e2e4").unwrap());
+        assert_eq!(
+            turn_indicator(&state).as_deref(),
+            Some("Move 1, Black to move")
+        );
+        state.game_result = Some(GameResult::DrawByAgreement);
+        assert_eq!(turn_indicator(&state), None);
+    }
+
+    #[test]
+    fn sticky_selection() {
+        use chess::piece::PieceType;
+        let state = BoardState::new();
+        let pos = |name: &str| -> Pos { name.parse().unwrap() };
+        let click = |selected: Option<&str>, clicked: &str, sticky: bool| {
+            click_action(&state, selected.map(pos), pos(clicked), sticky)
+        };
+        let knight = Piece::new(PieceColor::White, PieceType::Knight);
+        assert_eq!(click(None, "g1", false), ClickAction::Select(knight));
+        assert_eq!(click(None, "e4", true), ClickAction::Deselect);
+        assert_eq!(click(None, "e7", true), ClickAction::Deselect);
+        let e4 = state.from_uci("e2e4").unwrap();
+        for sticky in [false, true] {
+            assert_eq!(click(Some("e2"), "e4", sticky), ClickAction::Move(e4));
+            assert_eq!(click(Some("e2"), "e2", sticky), ClickAction::Deselect);
+            assert_eq!(click(Some("e2"), "e7", sticky), ClickAction::Deselect);
+        }
+        assert_eq!(click(Some("e2"), "g1", false), ClickAction::Deselect);
+        assert_eq!(click(Some("e2"), "g1", true), ClickAction::Select(knight));
+    }
+
+    #[test]
+    fn auto_promotion() {
+        let state = BoardState::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = state.from_uci("b7b8q").unwrap();
+        let knight = state.from_uci("b7b8n").unwrap();
+        assert_eq!(move_to_play(None, promotion), None);
+        assert_eq!(
+            move_to_play(Some(PromotedTo::Knight), promotion),
+            Some(knight)
+        );
+        assert_eq!(
+            move_to_play(Some(PromotedTo::Queen), promotion),
+            Some(promotion)
+        );
+        let king_move = state.from_uci("e1d1").unwrap();
+        assert_eq!(move_to_play(None, king_move), Some(king_move));
+        assert_eq!(
+            move_to_play(Some(PromotedTo::Rook), king_move),
+            Some(king_move)
+        );
+    }
+
+    #[test]
+    fn move_sounds() {
+        let mut state = BoardState::new();
+        let event = state.make_move(state.from_uci("e2e4").unwrap());
+        assert_eq!(sound_for(event), "./resources/sounds/move.mp3");
+        let events = [
+            MoveEvent::Move,
+            MoveEvent::Capture,
+            MoveEvent::Castle,
+            MoveEvent::Check,
+            MoveEvent::Promotion,
+            MoveEvent::GameEnd,
+        ];
+        let sounds: std::collections::HashSet<_> = events.map(sound_for).into();
+        assert_eq!(sounds.len(), events.len());
+        assert!(sounds.contains("./resources/sounds/game_end.mp3"));
+    }
+
+    #[test]
+    fn move_hints_visibility() {
+        assert!(should_show_move_hints(true, None));
+        assert!(!should_show_move_hints(false, None));
+        assert!(!should_show_move_hints(true, Some(3)));
+    }
+
+    #[test]
+    fn new_game_confirmation() {
+        assert!(!should_confirm_new_game(0, None));
+        assert!(should_confirm_new_game(3, None));
+        let result = GameResult::DrawByAgreement;
+        assert!(!should_confirm_new_game(3, Some(result)));
+    }
+
+    #[test]
+    fn hover_coordinates() {
+        // The pointer in the middle of the fifth column, fourth row from the top.
+        let cursor = (0.56, 0.44);
+        assert_eq!(
+            cursor_position_to_pos(cursor, PieceColor::White).to_string(),
+            "e5"
+        );
+        assert_eq!(
+            cursor_position_to_pos(cursor, PieceColor::Black).to_string(),
+            "d4"
+        );
+    }
+
+    #[test]
+    fn click_follows_side_to_move() {
+        let orientation = BoardOrientation::SideToMove;
+        let a1 = Pos::new(File::A, Rank::new(1));
+        let h8 = Pos::new(File::H, Rank::new(8));
+        // The bottom-left square is a1 for white and h8 for black.
+        let bottom = orientation.bottom(PieceColor::White);
+        assert_eq!(cursor_position_to_pos((0.01, 0.99), bottom), a1);
+        assert_eq!(oriented_square_coords(a1, bottom), (0, 7));
+        let bottom = orientation.bottom(PieceColor::Black);
+        assert_eq!(cursor_position_to_pos((0.01, 0.99), bottom), h8);
+        assert_eq!(oriented_square_coords(h8, bottom), (0, 7));
+    }
+}
@@ -0,0 +1,28 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Debug, PartialEq, Properties)]
+pub struct Props {
+    /// The viewed ply, the comment belongs to the move leading to it.
+    pub ply: usize,
+    pub comment: Option<String>,
+    pub on_edit: Callback<String>,
+}
+
+/// Shows the comment on the viewed move and lets the user edit it in place.
+#[function_component(CommentPanel)]
+pub fn comment_panel(props: &Props) -> Html {
+    if props.ply == 0 {
+        return html! {};
+    }
+    let onchange = props
+        .on_edit
+        .reform(|event: Event| event.target_unchecked_into::<HtmlInputElement>().value());
+    let value = props.comment.clone().unwrap_or_default();
+    html! {
+        <div class={classes!("flex", "flex-row", "items-center", "gap-2", "p-2")}>
+            <span>{ format!("Comment on ply {}:", props.ply) }</span>
+            <input type="text" {value} {onchange} placeholder="No comment"/>
+        </div>
+    }
+}
@@ -0,0 +1,614 @@
+//! A simple computer opponent: position evaluation and search.
+//!
+//! Scores are in centipawns.
+
+use crate::board::{BoardState, Square};
+use crate::moves::internal::Move;
+use crate::piece::{Piece, PieceColor, PieceType};
+use crate::pos::{Pos, Rank};
+
+mod book;
+
+pub use book::OpeningBook;
+
+pub fn piece_value(kind: PieceType) -> i32 {
+    match kind {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 320,
+        PieceType::Bishop => 330,
+        PieceType::Rook => 500,
+        PieceType::Queen => 900,
+        PieceType::King => 0,
+    }
+}
+
+// Piece-square tables, seen from White's side: the first row is the 8th rank.
+// From the Chess Programming Wiki "Simplified Evaluation Function".
+
+const PAWN_TABLE: [[i32; 8]; 8] = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [50, 50, 50, 50, 50, 50, 50, 50],
+    [10, 10, 20, 30, 30, 20, 10, 10],
+    [5, 5, 10, 25, 25, 10, 5, 5],
+    [0, 0, 0, 20, 20, 0, 0, 0],
+    [5, -5, -10, 0, 0, -10, -5, 5],
+    [5, 10, 10, -20, -20, 10, 10, 5],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+
+const KNIGHT_TABLE: [[i32; 8]; 8] = [
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+    [-40, -20, 0, 0, 0, 0, -20, -40],
+    [-30, 0, 10, 15, 15, 10, 0, -30],
+    [-30, 5, 15, 20, 20, 15, 5, -30],
+    [-30, 0, 15, 20, 20, 15, 0, -30],
+    [-30, 5, 10, 15, 15, 10, 5, -30],
+    [-40, -20, 0, 5, 5, 0, -20, -40],
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+];
+
+const BISHOP_TABLE: [[i32; 8]; 8] = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-10, 0, 5, 10, 10, 5, 0, -10],
+    [-10, 5, 5, 10, 10, 5, 5, -10],
+    [-10, 0, 10, 10, 10, 10, 0, -10],
+    [-10, 10, 10, 10, 10, 10, 10, -10],
+    [-10, 5, 0, 0, 0, 0, 5, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+
+const ROOK_TABLE: [[i32; 8]; 8] = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [5, 10, 10, 10, 10, 10, 10, 5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [0, 0, 0, 5, 5, 0, 0, 0],
+];
+
+const QUEEN_TABLE: [[i32; 8]; 8] = [
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-10, 0, 5, 5, 5, 5, 0, -10],
+    [-5, 0, 5, 5, 5, 5, 0, -5],
+    [0, 0, 5, 5, 5, 5, 0, -5],
+    [-10, 5, 5, 5, 5, 5, 0, -10],
+    [-10, 0, 5, 0, 0, 0, 0, -10],
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+];
+
+/// Middlegame table: the king should stay behind its pawns.
+const KING_TABLE: [[i32; 8]; 8] = [
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-20, -30, -30, -40, -40, -30, -30, -20],
+    [-10, -20, -20, -20, -20, -20, -20, -10],
+    [20, 20, 0, 0, 0, 0, 20, 20],
+    [20, 30, 10, 0, 0, 10, 30, 20],
+];
+
+/// Table value of a piece on a square. Black uses the tables mirrored vertically.
+pub fn square_value(kind: PieceType, color: PieceColor, pos: Pos) -> i32 {
+    let table = match kind {
+        PieceType::Pawn => &PAWN_TABLE,
+        PieceType::Knight => &KNIGHT_TABLE,
+        PieceType::Bishop => &BISHOP_TABLE,
+        PieceType::Rook => &ROOK_TABLE,
+        PieceType::Queen => &QUEEN_TABLE,
+        PieceType::King => &KING_TABLE,
+    };
+    let row = match color {
+        PieceColor::White => 8 - pos.rank.get(),
+        PieceColor::Black => pos.rank.get() - 1,
+    };
+    table[row as usize][pos.file.as_u8() as usize - 1]
+}
+
+impl BoardState {
+    /// Total value of the pieces of `color`.
+    pub fn material(&self, color: PieceColor) -> i32 {
+        self.pieces()
+            .filter(|(p, _)| p.color == color)
+            .map(|(p, _)| piece_value(p.kind))
+            .sum()
+    }
+
+    /// White's material minus Black's.
+    pub fn material_balance(&self) -> i32 {
+        self.material(PieceColor::White) - self.material(PieceColor::Black)
+    }
+
+    /// Sum of the piece-square table values of the pieces of `color`.
+    pub fn positional_score(&self, color: PieceColor) -> i32 {
+        self.pieces()
+            .filter(|(p, _)| p.color == color)
+            .map(|(p, pos)| square_value(p.kind, color, pos))
+            .sum()
+    }
+
+    /// Static exchange evaluation: the material won by `mv` when both sides keep recapturing
+    /// on the target square with their least valuable piece, each free to stop when behind.
+    ///
+    /// Pins are ignored, and moves which aren't captures are worth zero.
+    pub fn see(&self, mv: Move) -> i32 {
+        let (from, to, promoted) = match mv {
+            Move::Regular { from, to, promoted } => (from, to, promoted),
+            _ => return 0,
+        };
+        let mover = match self.square_by_pos(from) {
+            Square::Piece(piece) => piece,
+            Square::Empty => return 0,
+        };
+        let victim = match self.square_by_pos(to) {
+            Square::Piece(piece) => piece.kind,
+            Square::Empty if self.is_capture(mv) => PieceType::Pawn,
+            Square::Empty => return 0,
+        };
+        // Only the occupancy is updated, which is all the ray scans of `attackers_of` look at.
+        let mut board = self.clone();
+        *board.square_by_pos_mut(from) = Square::Empty;
+        let mut on_square = promoted.map_or(mover.kind, |p| p.to_piece(mover.color).kind);
+        let mut side = !mover.color;
+        let mut gains = vec![see_value(victim)];
+        while let Some((pos, kind)) = least_valuable_attacker(&board, to, side) {
+            gains.push(see_value(on_square) - gains.last().unwrap());
+            *board.square_by_pos_mut(pos) = Square::Empty;
+            on_square = kind;
+            side = !side;
+        }
+        // Going back from the end of the sequence, either side may decline to recapture.
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let previous = gains.last_mut().unwrap();
+            *previous = -(-*previous).max(last);
+        }
+        gains[0]
+    }
+}
+
+/// Like [`piece_value`], but a king can only be the last piece in an exchange.
+fn see_value(kind: PieceType) -> i32 {
+    match kind {
+        PieceType::King => 20_000,
+        _ => piece_value(kind),
+    }
+}
+
+fn least_valuable_attacker(
+    state: &BoardState,
+    pos: Pos,
+    by: PieceColor,
+) -> Option<(Pos, PieceType)> {
+    state
+        .attackers_of(pos, by)
+        .into_iter()
+        .filter_map(|p| match state.square_by_pos(p) {
+            Square::Piece(piece) => Some((p, piece.kind)),
+            Square::Empty => None,
+        })
+        .min_by_key(|(_, kind)| see_value(*kind))
+}
+
+/// Pawn weaknesses and strengths of one side, see [`BoardState::pawn_structure`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PawnStructure {
+    /// Pawns sharing the file with another pawn of the same color.
+    pub doubled: Vec<Pos>,
+    /// Pawns without pawns of the same color on the neighbouring files.
+    pub isolated: Vec<Pos>,
+    /// Pawns without opposing pawns in front of them on their own or neighbouring files.
+    pub passed: Vec<Pos>,
+}
+
+impl BoardState {
+    pub fn pawn_structure(&self, color: PieceColor) -> PawnStructure {
+        let pawns = |color| -> Vec<Pos> { self.pieces_of(PieceType::Pawn, color).collect() };
+        let (ours, theirs) = (pawns(color), pawns(!color));
+        // Pawns per file, padded with an empty file on both sides.
+        let mut files = [0u8; 10];
+        for pawn in &ours {
+            files[pawn.file.as_u8() as usize] += 1;
+        }
+        let is_ahead = |pawn: Pos, other: Pos| match color {
+            PieceColor::White => other.rank.get() > pawn.rank.get(),
+            PieceColor::Black => other.rank.get() < pawn.rank.get(),
+        };
+        let mut structure = PawnStructure::default();
+        for &pawn in &ours {
+            let file = pawn.file.as_u8() as usize;
+            if files[file] > 1 {
+                structure.doubled.push(pawn);
+            }
+            if files[file - 1] == 0 && files[file + 1] == 0 {
+                structure.isolated.push(pawn);
+            }
+            let blocked = theirs.iter().any(|&p| {
+                (p.file.as_u8() as i8 - pawn.file.as_u8() as i8).abs() <= 1 && is_ahead(pawn, p)
+            });
+            if !blocked {
+                structure.passed.push(pawn);
+            }
+        }
+        structure
+    }
+
+    /// Whether the king of `color` sits on its back rank with every square in front of it
+    /// blocked by its own pieces or covered by the opponent, while an enemy rook or queen
+    /// can reach that rank.
+    pub fn back_rank_weakness(&self, color: PieceColor) -> bool {
+        let back_rank = color.king_rank();
+        let king = match self.pieces_of(PieceType::King, color).next() {
+            Some(king) if king.rank == back_rank => king,
+            _ => return false,
+        };
+        let front_rank = match color {
+            PieceColor::White => Rank::new(2),
+            PieceColor::Black => Rank::new(7),
+        };
+        let has_luft = [king.file.prev(), Some(king.file), king.file.next()]
+            .into_iter()
+            .flatten()
+            .map(|file| Pos::new(file, front_rank))
+            .any(|pos| {
+                !self.is_square_occupied_by_color(pos, color) && !self.is_attacked(pos, !color)
+            });
+        if has_luft {
+            return false;
+        }
+        [PieceType::Rook, PieceType::Queen]
+            .into_iter()
+            .flat_map(|kind| self.pieces_of(kind, !color).map(move |pos| (kind, pos)))
+            .any(|(kind, pos)| {
+                let piece = Piece::new(!color, kind);
+                self.available_moves_iter(piece, pos)
+                    .filter_map(|mv| mv.to())
+                    .any(|to| to.rank == back_rank)
+            })
+    }
+}
+
+/// Static evaluation from the point of view of the side to move.
+pub fn evaluate(state: &BoardState) -> i32 {
+    let score = |color| state.material(color) + state.positional_score(color);
+    let us = state.side_to_move();
+    score(us) - score(!us)
+}
+
+/// Searches for a forced mate by the side to move in at most `n` of its moves.
+///
+/// Returns the shortest such mate as a line alternating the attacker's moves
+/// and one of the defender's replies. Every reply is checked, so the mate
+/// works against any defense.
+pub fn find_mate(state: &BoardState, n: u32) -> Option<Vec<Move>> {
+    let mut state = state.clone();
+    (1..=n).find_map(|depth| mate_in(&mut state, depth))
+}
+
+/// Like [`find_mate`] with exactly `n` moves. Moves are made on `state` and taken back
+/// before returning.
+fn mate_in(state: &mut BoardState, n: u32) -> Option<Vec<Move>> {
+    if n == 0 {
+        return None;
+    }
+    for mv in state.all_legal_moves() {
+        state.push_move(mv);
+        let line = mate_after(state, mv, n);
+        state.unmake_move();
+        if line.is_some() {
+            return line;
+        }
+    }
+    None
+}
+
+/// The mating line starting with `mv`, which was just made on `state`.
+fn mate_after(state: &mut BoardState, mv: Move, n: u32) -> Option<Vec<Move>> {
+    let replies = state.all_legal_moves();
+    if replies.is_empty() {
+        // Stalemate is no mate.
+        return state.in_check().then(|| vec![mv]);
+    }
+    if n == 1 {
+        return None;
+    }
+    let mut line = None;
+    for reply in replies {
+        state.push_move(reply);
+        let rest = mate_in(state, n - 1);
+        state.unmake_move();
+        match rest {
+            Some(rest) => {
+                line.get_or_insert_with(|| [vec![mv, reply], rest].concat());
+            }
+            None => return None,
+        }
+    }
+    line
+}
+
+/// Score of being checkmated. Mates found sooner score further from zero.
+const MATE: i32 = 1_000_000;
+
+thread_local! {
+    static BOOK: OpeningBook = OpeningBook::standard();
+}
+
+/// The move to play: a book move while the game is in the opening book, otherwise the
+/// result of a search `depth` plies deep. `seed` chooses between book moves.
+pub fn best_move(state: &BoardState, depth: u32, seed: u64) -> Option<Move> {
+    BOOK.with(|book| book.pick(state, seed))
+        .or_else(|| search(state, depth).map(|(mv, _)| mv))
+}
+
+/// How strong the computer opponent plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+/// How the engine plays at a [`Difficulty`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchParams {
+    /// Search depth in plies.
+    pub depth: u32,
+    /// Chance in percent to play a random legal move instead of the best one.
+    pub blunder_chance: u8,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Self::Easy, Self::Medium, Self::Hard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Medium => "Medium",
+            Self::Hard => "Hard",
+        }
+    }
+
+    /// Easy sees only its own move and blunders often enough for a beginner to win.
+    pub fn search_params(self) -> SearchParams {
+        let (depth, blunder_chance) = match self {
+            Self::Easy => (1, 30),
+            Self::Medium => (2, 10),
+            Self::Hard => (3, 0),
+        };
+        SearchParams {
+            depth,
+            blunder_chance,
+        }
+    }
+}
+
+/// The computer's move at `difficulty`. `seed` decides whether it blunders and which
+/// move it plays then, as well as the choice between book moves.
+pub fn computer_move(state: &BoardState, difficulty: Difficulty, seed: u64) -> Option<Move> {
+    let params = difficulty.search_params();
+    let (roll, seed) = (seed % 100, seed / 100);
+    if roll < params.blunder_chance as u64 {
+        let moves = state.all_legal_moves();
+        if !moves.is_empty() {
+            return Some(moves[(seed % moves.len() as u64) as usize]);
+        }
+    }
+    best_move(state, params.depth, seed)
+}
+
+/// Alpha-beta search returning the best move for the side to move and its score.
+pub fn search(state: &BoardState, depth: u32) -> Option<(Move, i32)> {
+    let mut state = state.clone();
+    let mut best = None;
+    let mut alpha = -MATE - 1;
+    for mv in ordered_moves(&state) {
+        state.push_move(mv);
+        let score = -negamax(&mut state, depth.saturating_sub(1), 1, -MATE - 1, -alpha);
+        state.unmake_move();
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some((mv, score));
+        }
+    }
+    best
+}
+
+/// The score of `state` for the side to move. Moves are made on `state` and taken back
+/// before returning.
+fn negamax(state: &mut BoardState, depth: u32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+    // A position seen before in the game or the searched line can be repeated until it's
+    // a draw, which the losing side is happy to take.
+    if state.repetition_count() > 1 {
+        return 0;
+    }
+    let moves = ordered_moves(state);
+    if moves.is_empty() {
+        return match state.in_check() {
+            true => -MATE + ply,
+            false => 0,
+        };
+    }
+    if depth == 0 {
+        return evaluate(state);
+    }
+    for mv in moves {
+        state.push_move(mv);
+        let score = -negamax(state, depth - 1, ply + 1, -beta, -alpha);
+        state.unmake_move();
+        if score >= beta {
+            return beta;
+        }
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+/// Legal moves with captures first, which makes cutoffs more likely.
+fn ordered_moves(state: &BoardState) -> Vec<Move> {
+    let mut moves = state.all_legal_moves();
+    moves.sort_by_key(|mv| !state.is_capture(*mv));
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centralized_knight() {
+        let d4 = BoardState::from_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").unwrap();
+        let a1 = BoardState::from_fen("4k3/8/8/8/8/8/8/N3K3 w - - 0 1").unwrap();
+        assert!(d4.positional_score(PieceColor::White) > a1.positional_score(PieceColor::White));
+        assert!(evaluate(&d4) > evaluate(&a1));
+    }
+
+    #[test]
+    fn symmetric_tables() {
+        let start = BoardState::new();
+        assert_eq!(
+            start.positional_score(PieceColor::White),
+            start.positional_score(PieceColor::Black)
+        );
+        assert_eq!(start.material_balance(), 0);
+        assert_eq!(evaluate(&start), 0);
+        let e4 = "e4".parse().unwrap();
+        let e5 = "e5".parse().unwrap();
+        assert_eq!(
+            square_value(PieceType::Pawn, PieceColor::White, e4),
+            square_value(PieceType::Pawn, PieceColor::Black, e5)
+        );
+    }
+
+    #[test]
+    fn pawn_weaknesses() {
+        let state = BoardState::from_fen("4k3/1p6/8/8/8/2P5/P1PP4/4K3 w - - 0 1").unwrap();
+        let squares = |squares: Vec<Pos>| -> Vec<String> {
+            let mut squares: Vec<_> = squares.iter().map(ToString::to_string).collect();
+            squares.sort();
+            squares
+        };
+        let white = state.pawn_structure(PieceColor::White);
+        assert_eq!(squares(white.doubled), ["c2", "c3"]);
+        assert_eq!(squares(white.isolated), ["a2"]);
+        assert_eq!(squares(white.passed), ["d2"]);
+        let black = state.pawn_structure(PieceColor::Black);
+        assert_eq!(black.isolated.len(), 1);
+        assert!(black.passed.is_empty());
+    }
+
+    #[test]
+    fn back_rank() {
+        let weak = BoardState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert!(weak.back_rank_weakness(PieceColor::Black));
+        assert!(!weak.back_rank_weakness(PieceColor::White));
+        let luft = BoardState::from_fen("6k1/5pp1/7p/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert!(!luft.back_rank_weakness(PieceColor::Black));
+        // Nothing can reach the back rank.
+        let no_attacker = BoardState::from_fen("6k1/5ppp/8/8/8/8/8/B5K1 w - - 0 1").unwrap();
+        assert!(!no_attacker.back_rank_weakness(PieceColor::Black));
+    }
+
+    #[test]
+    fn static_exchange() {
+        // The pawn is lost for the queen.
+        let state = BoardState::from_fen("4k3/8/4p3/3q4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(state.see(state.from_uci("e4d5").unwrap()), 800);
+        // Taking a defended pawn with the queen loses the queen.
+        let state = BoardState::from_fen("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        assert_eq!(state.see(state.from_uci("d1d5").unwrap()), -800);
+        // A free rook.
+        let state = BoardState::from_fen("4k3/8/8/3r4/8/2N5/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(state.see(state.from_uci("c3d5").unwrap()), 500);
+        // The rooks stacked behind join the exchange once the file opens:
+        // Rxd5 Qxd5 Rxd5 Rxd5 wins a pawn and the queen for both rooks.
+        let state = BoardState::from_fen("3rk3/3q4/8/3p4/8/8/3R4/3RK3 w - - 0 1").unwrap();
+        assert_eq!(state.see(state.from_uci("d2d5").unwrap()), 0);
+    }
+
+    #[test]
+    fn mate_in_two() {
+        let state = BoardState::from_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1").unwrap();
+        assert_eq!(find_mate(&state, 1), None);
+        let line = find_mate(&state, 2).unwrap();
+        assert_eq!(line.len(), 3);
+
+        // The first move mates in one against every defense.
+        let mut after_first = state.clone();
+        after_first.make_move(line[0]);
+        for reply in after_first.all_legal_moves() {
+            let mut next = after_first.clone();
+            next.make_move(reply);
+            assert!(find_mate(&next, 1).is_some());
+        }
+
+        let mut end = state;
+        for mv in line {
+            end.make_move(mv);
+        }
+        assert!(end.is_checkmate(PieceColor::Black));
+    }
+
+    #[test]
+    fn book_then_search() {
+        let start = BoardState::new();
+        let first_moves = ["e2e4", "d2d4", "c2c4", "g1f3"].map(|uci| start.from_uci(uci));
+        for seed in 0..10 {
+            assert!(first_moves.contains(&best_move(&start, 2, seed)));
+        }
+        // Out of book, the search takes the free queen.
+        let state = BoardState::from_fen("4k3/8/8/3q4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(best_move(&state, 2, 0), state.from_uci("e4d5"));
+        let mated = BoardState::from_fen(crate::fen::KnownPosition::BackRankMate.fen()).unwrap();
+        assert_eq!(best_move(&mated, 2, 0), None);
+    }
+
+    #[test]
+    fn repetitions() {
+        let after = |fen: &str, moves: &[&str]| {
+            let mut state = BoardState::from_fen(fen).unwrap();
+            for uci in moves {
+                state.make_move(state.from_uci(uci).unwrap());
+            }
+            state
+        };
+        // The kings walked back and forth, going to g1 again repeats a position.
+        let shuffle = ["h1g1", "h8g8", "g1h1", "g8h8"];
+        let behind = after("1q5k/8/8/8/8/8/8/7K w - - 0 1", &shuffle);
+        let (mv, score) = search(&behind, 2).unwrap();
+        assert_eq!(behind.to_uci(mv), "h1g1");
+        assert_eq!(score, 0);
+        let ahead = after("7k/8/8/8/8/8/8/1Q5K w - - 0 1", &shuffle);
+        let (mv, score) = search(&ahead, 2).unwrap();
+        assert_ne!(ahead.to_uci(mv), "h1g1");
+        assert!(score > 0);
+    }
+
+    #[test]
+    fn difficulty_levels() {
+        let params = Difficulty::ALL.map(Difficulty::search_params);
+        assert!(params[0].depth < params[2].depth);
+        assert!(params[0].blunder_chance > params[2].blunder_chance);
+        assert_eq!(params[2].blunder_chance, 0);
+        // Hard never gives up the free queen, Easy sometimes does.
+        let state = BoardState::from_fen("4k3/8/8/3q4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let take = state.from_uci("e4d5");
+        assert!((0..20).all(|seed| computer_move(&state, Difficulty::Hard, seed) == take));
+        assert!((0..200).any(|seed| computer_move(&state, Difficulty::Easy, seed) != take));
+        // The last two digits of the seed decide whether to blunder (below 30 for Easy),
+        // the rest which legal move to play then.
+        let easy = |seed| state.to_uci(computer_move(&state, Difficulty::Easy, seed).unwrap());
+        assert_eq!(easy(50), "e4d5");
+        assert_eq!(easy(130), "e4d5");
+        assert_eq!(easy(0), "e4e5");
+        assert_eq!(easy(29), "e4e5");
+        assert_eq!(easy(200), "e1e2");
+        assert_eq!(easy(300), "e1f1");
+        assert_eq!(easy(400), "e1f2");
+    }
+}
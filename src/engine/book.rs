@@ -0,0 +1,115 @@
+use crate::board::BoardState;
+use crate::moves::internal::Move;
+use std::collections::HashMap;
+
+/// Mainline openings in UCI notation with how often to play them. Lines sharing their
+/// first moves add up, so `e2e4` is weighted by all the 1. e4 openings together.
+const LINES: &[(&str, u32)] = &[
+    // Ruy Lopez and Italian Game.
+    ("e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6", 40),
+    ("e2e4 e7e5 g1f3 b8c6 f1c4 f8c5 c2c3", 25),
+    // Open Sicilian.
+    ("e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4 g8f6 b1c3", 35),
+    ("e2e4 e7e6 d2d4 d7d5 b1c3", 15),
+    ("e2e4 c7c6 d2d4 d7d5 b1c3", 15),
+    // Queen's Gambit Declined and Slav.
+    ("d2d4 d7d5 c2c4 e7e6 b1c3 g8f6 c1g5", 30),
+    ("d2d4 d7d5 c2c4 c7c6 g1f3 g8f6", 20),
+    // Nimzo-Indian and King's Indian.
+    ("d2d4 g8f6 c2c4 e7e6 b1c3 f8b4", 20),
+    ("d2d4 g8f6 c2c4 g7g6 b1c3 f8g7 e2e4 d7d6", 20),
+    ("c2c4 e7e5 b1c3 g8f6", 10),
+    ("g1f3 d7d5 g2g3 g8f6 f1g2", 10),
+];
+
+/// Weighted book moves by position, found with [`BoardState::position_hash`].
+#[derive(Debug, Clone, Default)]
+pub struct OpeningBook {
+    moves: HashMap<u64, Vec<(Move, u32)>>,
+}
+
+impl OpeningBook {
+    /// The book built into the engine.
+    pub fn standard() -> Self {
+        Self::from_lines(LINES)
+    }
+
+    /// Builds a book from lines of UCI moves played from the starting position.
+    ///
+    /// Panics on an illegal move, the lines are expected to be checked in advance.
+    pub fn from_lines(lines: &[(&str, u32)]) -> Self {
+        let mut book = Self::default();
+        for &(line, weight) in lines {
+            let mut state = BoardState::new();
+            for uci in line.split_whitespace() {
+                let mv = state
+                    .from_uci(uci)
+                    .unwrap_or_else(|| panic!("illegal book move {uci} in {line:?}"));
+                let moves = book.moves.entry(state.position_hash()).or_default();
+                match moves.iter_mut().find(|(m, _)| *m == mv) {
+                    Some((_, w)) => *w += weight,
+                    None => moves.push((mv, weight)),
+                }
+                state.make_move(mv);
+            }
+        }
+        book
+    }
+
+    /// Book moves in the position with their weights, empty when out of book.
+    pub fn moves(&self, state: &BoardState) -> &[(Move, u32)] {
+        self.moves
+            .get(&state.position_hash())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Picks a book move with a probability proportional to its weight. Equal seeds
+    /// give equal moves.
+    pub fn pick(&self, state: &BoardState, seed: u64) -> Option<Move> {
+        let moves = self.moves(state);
+        let total: u64 = moves.iter().map(|(_, w)| *w as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut target = seed % total;
+        for &(mv, weight) in moves {
+            if target < weight as u64 {
+                return Some(mv);
+            }
+            target -= weight as u64;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_book() {
+        let book = OpeningBook::standard();
+        let start = BoardState::new();
+        let first_moves: Vec<_> = book
+            .moves(&start)
+            .iter()
+            .map(|(mv, _)| start.to_uci(*mv))
+            .collect();
+        assert_eq!(first_moves, ["e2e4", "d2d4", "c2c4", "g1f3"]);
+        let picked: Vec<_> = (0..300)
+            .filter_map(|seed| book.pick(&start, seed))
+            .collect();
+        assert_eq!(picked.len(), 300);
+        assert!(picked.contains(&start.from_uci("e2e4").unwrap()));
+        assert!(picked.contains(&start.from_uci("g1f3").unwrap()));
+        // Transpositions are found through the position hash.
+        let mut state = BoardState::new();
+        for uci in ["g1f3", "b8c6", "e2e4", "e7e5"] {
+            state.make_move(state.from_uci(uci).unwrap());
+        }
+        assert_eq!(book.pick(&state, 0), state.from_uci("f1b5"));
+        assert!(book
+            .moves(&BoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap())
+            .is_empty());
+    }
+}
@@ -1,12 +1,254 @@
+use crate::board::BoardBuilder;
+use crate::board::BoardState;
+use crate::board::PositionError;
 use crate::board::Square;
+use crate::moves::CastlingSide;
 use crate::piece::Piece;
 use crate::piece::PieceColor;
 use crate::piece::PieceType;
+use crate::pos::Pos;
+use std::fmt;
 
 pub const STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
 pub const FOOLS_MATE: &str = "rnbqkbnr/pppppppp/8/7Q/2B5/8/PPPP1PPP/RNB1K1NR";
 pub const TWO_KINGS: &str = "4k3/8/8/8/8/8/8/3K4";
 
+/// Standard positions used by move generation tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownPosition {
+    Starting,
+    FoolsMate,
+    TwoKings,
+    /// The "Kiwipete" perft position from the Chess Programming Wiki.
+    Kiwipete,
+    /// White can capture f5 en passant.
+    EnPassant,
+    /// Both sides have pawns about to promote, with and without captures.
+    Promotion,
+    /// Black has only a king, which isn't in check but can't move.
+    KingStalemate,
+    BackRankMate,
+    /// Black's king is mated by a knight, surrounded by its own pieces.
+    SmotheredMate,
+}
+
+impl KnownPosition {
+    pub const ALL: [KnownPosition; 9] = [
+        Self::Starting,
+        Self::FoolsMate,
+        Self::TwoKings,
+        Self::Kiwipete,
+        Self::EnPassant,
+        Self::Promotion,
+        Self::KingStalemate,
+        Self::BackRankMate,
+        Self::SmotheredMate,
+    ];
+
+    pub fn fen(self) -> &'static str {
+        match self {
+            Self::Starting => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            Self::FoolsMate => "rnbqkbnr/pppppppp/8/7Q/2B5/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1",
+            Self::TwoKings => "4k3/8/8/8/8/8/8/3K4 w - - 0 1",
+            Self::Kiwipete => {
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            }
+            Self::EnPassant => "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            Self::Promotion => "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            Self::KingStalemate => "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+            Self::BackRankMate => "R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+            Self::SmotheredMate => "6rk/5Npp/8/8/8/8/8/6K1 b - - 0 1",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenError {
+    /// Expected piece placement, side to move, castling, en passant and,
+    /// optionally, the two move counters.
+    FieldCount,
+    Placement,
+    Turn,
+    Castling,
+    EnPassant,
+    MoveCounter,
+    Position(PositionError),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FieldCount => write!(f, "wrong number of FEN fields"),
+            Self::Placement => write!(f, "invalid piece placement"),
+            Self::Turn => write!(f, "side to move must be 'w' or 'b'"),
+            Self::Castling => write!(f, "invalid castling rights"),
+            Self::EnPassant => write!(f, "invalid en passant square"),
+            Self::MoveCounter => write!(f, "invalid move counter"),
+            Self::Position(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl BoardState {
+    /// Parses a full FEN record. The move counters may be omitted.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::FieldCount);
+        }
+        let mut builder = BoardBuilder::new();
+        for (index, square) in try_parse_placement(fields[0])?.into_iter().enumerate() {
+            if let Square::Piece(piece) = square {
+                builder = builder.piece(BoardState::pos_by_square_index(index), piece);
+            }
+        }
+        builder = match fields[1] {
+            "w" => builder.turn(PieceColor::White),
+            "b" => builder.turn(PieceColor::Black),
+            _ => return Err(FenError::Turn),
+        };
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let (color, side) = match c {
+                    'K' => (PieceColor::White, CastlingSide::Short),
+                    'Q' => (PieceColor::White, CastlingSide::Long),
+                    'k' => (PieceColor::Black, CastlingSide::Short),
+                    'q' => (PieceColor::Black, CastlingSide::Long),
+                    _ => return Err(FenError::Castling),
+                };
+                builder = builder.castling(color, side);
+            }
+        }
+        if fields[3] != "-" {
+            let pos: Pos = fields[3].parse().map_err(|_| FenError::EnPassant)?;
+            builder = builder.en_passant(pos);
+        }
+        if let [halfmove_clock, move_number] = fields[4..] {
+            let halfmove_clock = halfmove_clock.parse().map_err(|_| FenError::MoveCounter)?;
+            let move_number = move_number.parse().map_err(|_| FenError::MoveCounter)?;
+            if move_number == 0 {
+                return Err(FenError::MoveCounter);
+            }
+            builder = builder
+                .halfmove_clock(halfmove_clock)
+                .move_number(move_number);
+        }
+        // Castling rights and en passant squares are often copied around carelessly, so
+        // impossible ones are ignored.
+        builder
+            .drop_invalid_castling()
+            .drop_invalid_en_passant()
+            .build()
+            .map_err(FenError::Position)
+    }
+}
+
+impl BoardState {
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for (rank, squares) in self.squares.chunks(8).enumerate() {
+            if rank > 0 {
+                placement.push('/');
+            }
+            let mut empty = 0;
+            for square in squares {
+                match square {
+                    Square::Empty => empty += 1,
+                    Square::Piece(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece_char(*piece));
+                    }
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+        }
+        let turn = match self.turn {
+            PieceColor::White => "w",
+            PieceColor::Black => "b",
+        };
+        let mut castling = String::new();
+        for (color, side, c) in [
+            (PieceColor::White, CastlingSide::Short, 'K'),
+            (PieceColor::White, CastlingSide::Long, 'Q'),
+            (PieceColor::Black, CastlingSide::Short, 'k'),
+            (PieceColor::Black, CastlingSide::Long, 'q'),
+        ] {
+            if self.has_castling_right(color, side) {
+                castling.push(c);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        let en_passant = self
+            .en_passant()
+            .map(|pos| pos.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        format!(
+            "{placement} {turn} {castling} {en_passant} {} {}",
+            self.plies_since_last_non_repeatable_move(),
+            self.move_number()
+        )
+    }
+}
+
+fn piece_char(piece: Piece) -> char {
+    let c = match piece.kind {
+        PieceType::Pawn => 'p',
+        PieceType::King => 'k',
+        PieceType::Queen => 'q',
+        PieceType::Rook => 'r',
+        PieceType::Bishop => 'b',
+        PieceType::Knight => 'n',
+    };
+    match piece.color {
+        PieceColor::White => c.to_ascii_uppercase(),
+        PieceColor::Black => c,
+    }
+}
+
+/// Squares that differ between two positions, with their contents before and after.
+/// Only the piece placement of the FEN records is compared, and it has to be valid in both.
+pub fn fen_diff(before: &str, after: &str) -> Result<Vec<(Pos, Square, Square)>, FenError> {
+    let placement =
+        |fen: &str| try_parse_placement(fen.split_whitespace().next().unwrap_or_default());
+    let diff = placement(before)?
+        .into_iter()
+        .zip(placement(after)?)
+        .enumerate()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(index, (before, after))| (BoardState::pos_by_square_index(index), before, after))
+        .collect();
+    Ok(diff)
+}
+
+/// Like [`parse_fen`], but checks that there are exactly 8 ranks of 8 squares.
+fn try_parse_placement(placement: &str) -> Result<Vec<Square>, FenError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::Placement);
+    }
+    for rank in ranks {
+        let mut width = 0;
+        for c in rank.chars() {
+            width += match c {
+                '1'..='8' => c.to_digit(10).unwrap(),
+                'p' | 'k' | 'q' | 'r' | 'b' | 'n' | 'P' | 'K' | 'Q' | 'R' | 'B' | 'N' => 1,
+                _ => return Err(FenError::Placement),
+            };
+        }
+        if width != 8 {
+            return Err(FenError::Placement);
+        }
+    }
+    Ok(parse_fen(placement))
+}
+
 pub fn parse_fen(fen: &str) -> Vec<Square> {
     let mut result = Vec::new();
     let ranks = fen.split("/");
@@ -38,3 +280,162 @@ pub fn parse_fen(fen: &str) -> Vec<Square> {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::internal::Move;
+
+    #[test]
+    fn known_positions() {
+        for position in KnownPosition::ALL {
+            let state = BoardState::from_fen(position.fen())
+                .unwrap_or_else(|err| panic!("{position:?}: {err}"));
+            for color in [PieceColor::White, PieceColor::Black] {
+                let pieces: Vec<_> = state.pieces().filter(|(p, _)| p.color == color).collect();
+                let pawns = pieces
+                    .iter()
+                    .filter(|(p, _)| p.kind == PieceType::Pawn)
+                    .count();
+                assert!(pieces.len() <= 16 && pawns <= 8, "{position:?}");
+            }
+        }
+        let kiwipete = BoardState::from_fen(KnownPosition::Kiwipete.fen()).unwrap();
+        assert_eq!(kiwipete.pieces().count(), 32);
+        assert!(kiwipete.is_castling_possible(CastlingSide::Long));
+        let en_passant = BoardState::from_fen(KnownPosition::EnPassant.fen()).unwrap();
+        assert_eq!(en_passant.en_passant(), Some("f6".parse().unwrap()));
+        assert_eq!(en_passant.move_number(), 3);
+    }
+
+    #[test]
+    fn invalid_fen() {
+        assert_eq!(
+            BoardState::from_fen("8/8/8 w - -").unwrap_err(),
+            FenError::Placement
+        );
+        assert_eq!(
+            BoardState::from_fen("4k3/8/8/8/8/8/8/4K3 x - -").unwrap_err(),
+            FenError::Turn
+        );
+        assert_eq!(
+            BoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w").unwrap_err(),
+            FenError::FieldCount
+        );
+    }
+
+    #[test]
+    fn independent_castling_rights() {
+        let castlings = |state: &BoardState| -> Vec<Move> {
+            state
+                .all_legal_moves()
+                .into_iter()
+                .filter(|mv| matches!(mv, Move::Castling { .. }))
+                .collect()
+        };
+        let mut state = BoardState::from_fen("r3k2r/8/8/8/R7/8/8/R3K2R w Qk - 0 1").unwrap();
+        assert_eq!(castlings(&state), [Move::castling(CastlingSide::Long)]);
+        // The extra rook on the a-file isn't the one castling long.
+        state.make_move(state.from_uci("a4a5").unwrap());
+        assert_eq!(castlings(&state), [Move::castling(CastlingSide::Short)]);
+        state.make_move(state.from_uci("a8b8").unwrap());
+        assert_eq!(castlings(&state), [Move::castling(CastlingSide::Long)]);
+        // Capturing the rook on h8 takes away Black's short castling.
+        state.make_move(state.from_uci("h1h8").unwrap());
+        assert!(!state.has_castling_right(PieceColor::Black, CastlingSide::Short));
+        assert!(castlings(&state).is_empty());
+        assert_eq!(state.to_fen(), "1r2k2R/8/8/R7/8/8/8/R3K3 b Q - 0 2");
+    }
+
+    #[test]
+    fn en_passant_round_trip() {
+        let mut state = BoardState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        let capture = state.from_uci("e5d6").unwrap();
+        assert!(state.all_legal_moves().contains(&capture));
+        assert!(state.is_capture(capture));
+        state.make_move(capture);
+        assert_eq!(state.to_fen(), "4k3/8/3P4/8/8/8/8/4K3 b - - 0 2");
+        // A double push sets the target for the reply only.
+        let mut state = BoardState::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        state.make_move(state.from_uci("e2e4").unwrap());
+        assert_eq!(state.to_fen(), "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1");
+        state.make_move(state.from_uci("e8d8").unwrap());
+        assert_eq!(state.to_fen(), "3k4/8/8/8/3pP3/8/8/4K3 w - - 1 2");
+    }
+
+    #[test]
+    fn impossible_castling_rights() {
+        // No rook on h1, the black king isn't on e8.
+        let state = BoardState::from_fen("r2k3r/8/8/8/8/8/8/R3K3 w KQkq - 0 1").unwrap();
+        assert!(!state.has_castling_right(PieceColor::White, CastlingSide::Short));
+        assert!(state.has_castling_right(PieceColor::White, CastlingSide::Long));
+        assert!(!state.has_castling_right(PieceColor::Black, CastlingSide::Short));
+        assert!(!state.has_castling_right(PieceColor::Black, CastlingSide::Long));
+        assert_eq!(state.to_fen(), "r2k3r/8/8/8/8/8/8/R3K3 w Q - 0 1");
+    }
+
+    #[test]
+    fn king_off_home_square() {
+        // Both rooks are home, but the king isn't.
+        let state = BoardState::from_fen("4k3/8/8/8/8/8/8/R2K3R w KQ - 0 1").unwrap();
+        assert!(!state.has_castling_right(PieceColor::White, CastlingSide::Short));
+        assert!(!state.has_castling_right(PieceColor::White, CastlingSide::Long));
+        assert!(!state
+            .all_legal_moves()
+            .iter()
+            .any(|mv| matches!(mv, Move::Castling { .. })));
+        assert_eq!(state.to_fen(), "4k3/8/8/8/8/8/8/R2K3R w - - 0 1");
+    }
+
+    #[test]
+    fn phantom_en_passant() {
+        // No black pawn could have just skipped d6.
+        let state = BoardState::from_fen("4k3/8/8/3P4/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(state.en_passant(), None);
+        assert!(state.from_uci("d5d6").is_some());
+        // The pawn on d7 can't have skipped it either.
+        let state = BoardState::from_fen("4k3/3p4/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(state.en_passant(), None);
+        let state = BoardState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(state.en_passant(), Some("d6".parse().unwrap()));
+    }
+
+    #[test]
+    fn fen_roundtrip() {
+        for position in KnownPosition::ALL {
+            let state = BoardState::from_fen(position.fen()).unwrap();
+            assert_eq!(state.to_fen(), position.fen());
+        }
+    }
+
+    #[test]
+    fn pawn_push_diff() {
+        let mut state = BoardState::new();
+        let before = state.to_fen();
+        state.make_move(state.from_uci("e2e4").unwrap());
+        let after = state.to_fen();
+        assert_eq!(
+            after,
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+        let pawn = Square::Piece(Piece {
+            kind: PieceType::Pawn,
+            color: PieceColor::White,
+        });
+        assert_eq!(
+            fen_diff(&before, &after).unwrap(),
+            vec![
+                ("e4".parse().unwrap(), Square::Empty, pawn),
+                ("e2".parse().unwrap(), pawn, Square::Empty),
+            ]
+        );
+        assert_eq!(
+            fen_diff(&before, "8/8/8 w - - 0 1"),
+            Err(FenError::Placement)
+        );
+        assert_eq!(
+            fen_diff("4x3/8/8/8/8/8/8/8", &after),
+            Err(FenError::Placement)
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::rc::Rc;
+
+use chess::pgn::{Game, PgnError};
+use web_sys::HtmlTextAreaElement;
+use yew::prelude::*;
+
+/// How a game is listed, like `Carlsen – Nakamura, 1-0`.
+pub fn game_summary(game: &Game) -> String {
+    format!("{} – {}, {}", game.tags.white, game.tags.black, game.result)
+}
+
+#[derive(Debug, PartialEq, Properties)]
+pub struct Props {
+    /// The games of the loaded collection, including the ones that failed to parse.
+    pub games: Rc<Vec<Result<Game, PgnError>>>,
+    pub selected: Option<usize>,
+    /// Why the picked game couldn't be loaded.
+    pub error: Option<String>,
+    /// Called with pasted PGN text.
+    pub on_load: Callback<String>,
+    pub on_select: Callback<usize>,
+}
+
+/// Loads a PGN collection and lists its games to pick one for replay.
+#[function_component(GameNavigator)]
+pub fn game_navigator(props: &Props) -> Html {
+    let onchange = props
+        .on_load
+        .reform(|event: Event| event.target_unchecked_into::<HtmlTextAreaElement>().value());
+    let games = props
+        .games
+        .iter()
+        .enumerate()
+        .map(|(index, game)| match game {
+            Ok(game) => {
+                let onclick = props.on_select.reform(move |_| index);
+                let class = classes!((props.selected == Some(index)).then_some("font-bold"));
+                html! {
+                    <button {onclick} {class}>{ game_summary(game) }</button>
+                }
+            }
+            Err(err) => html! {
+                <button disabled={true}>{ format!("Game {}: {err}", index + 1) }</button>
+            },
+        });
+    let error = props
+        .error
+        .as_ref()
+        .map(|error| html! { <span>{ format!("Can't load the game: {error}") }</span> });
+    let empty = props
+        .games
+        .is_empty()
+        .then(|| html! { <span>{ "No games loaded" }</span> });
+    html! {
+        <div class={classes!("flex", "flex-col", "gap-2", "p-2")}>
+            <textarea {onchange} placeholder="Paste PGN games"/>
+            { for error }
+            { for empty }
+            { for games }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess::pgn::from_pgn_collection;
+
+    #[test]
+    fn summaries() {
+        let pgn = "[White \"Carlsen\"]\n[Black \"Nakamura\"]\n[Result \"1-0\"]\n\n1. e4 1-0\n\n\
+                   [White \"Firouzja\"]\n\n1. d4 d5 *";
+        let summaries: Vec<_> = from_pgn_collection(pgn)
+            .iter()
+            .map(|game| game_summary(game.as_ref().unwrap()))
+            .collect();
+        assert_eq!(summaries, ["Carlsen – Nakamura, 1-0", "Firouzja – ?, *"]);
+    }
+}
@@ -0,0 +1,72 @@
+use yew::prelude::*;
+
+use chess::moves::Ply;
+
+/// How close the game is to a fifty-move draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FiftyMoveStatus {
+    Normal,
+    /// 40 moves without a capture or a pawn move.
+    ClaimableSoon,
+    Claimable,
+}
+
+pub fn fifty_move_status(plies: Ply) -> FiftyMoveStatus {
+    match plies {
+        0..=79 => FiftyMoveStatus::Normal,
+        80..=99 => FiftyMoveStatus::ClaimableSoon,
+        _ => FiftyMoveStatus::Claimable,
+    }
+}
+
+pub fn fifty_move_hint(status: FiftyMoveStatus) -> Option<&'static str> {
+    match status {
+        FiftyMoveStatus::Normal => None,
+        FiftyMoveStatus::ClaimableSoon => Some("Draw claimable soon"),
+        FiftyMoveStatus::Claimable => Some("Draw can be claimed"),
+    }
+}
+
+#[derive(Debug, PartialEq, Properties)]
+pub struct Props {
+    pub plies: Ply,
+    pub on_claim: Callback<MouseEvent>,
+}
+
+#[function_component(HalfmoveClock)]
+pub fn halfmove_clock(props: &Props) -> Html {
+    let status = fifty_move_status(props.plies);
+    let claim_button = (status == FiftyMoveStatus::Claimable).then(|| {
+        html! {
+            <button onclick={props.on_claim.clone()}>{ "Claim draw" }</button>
+        }
+    });
+    html! {
+        <div class={classes!("flex", "flex-row", "items-center", "gap-2")}>
+            <span title="Plies since the last capture or pawn move">
+                { format!("Halfmove clock: {}", props.plies) }
+            </span>
+            { for fifty_move_hint(status) }
+            { for claim_button }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds() {
+        assert_eq!(fifty_move_status(0), FiftyMoveStatus::Normal);
+        assert_eq!(fifty_move_status(79), FiftyMoveStatus::Normal);
+        assert_eq!(fifty_move_status(80), FiftyMoveStatus::ClaimableSoon);
+        assert_eq!(fifty_move_status(99), FiftyMoveStatus::ClaimableSoon);
+        assert_eq!(fifty_move_status(100), FiftyMoveStatus::Claimable);
+        assert_eq!(fifty_move_hint(FiftyMoveStatus::Normal), None);
+        assert_eq!(
+            fifty_move_hint(FiftyMoveStatus::ClaimableSoon),
+            Some("Draw claimable soon")
+        );
+    }
+}
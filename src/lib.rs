@@ -0,0 +1,10 @@
+pub mod board;
+pub mod engine;
+pub mod fen;
+pub mod moves;
+pub mod pgn;
+pub mod piece;
+pub mod pos;
+pub mod puzzle;
+pub mod render;
+mod take_while;
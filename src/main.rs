@@ -1,49 +1,127 @@
-#![feature(bool_to_option)]
-
-use move_list::MoveList;
-use moves::PromotedTo;
-use std::cell::RefCell;
+use chess::board::BoardState;
+use chess::engine::Difficulty;
+use chess::moves::san;
+use chess::moves::{MoveNumber, PromotedTo};
+use chess::piece::PieceColor;
+use chess::pos::Pos;
+use chess::render::{BoardOrientation, BoardTheme, HighlightStyle};
+use move_list::{AnnotatedMove, MoveList};
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use yew::prelude::*;
 
-mod board;
-mod fen;
+mod board_view;
+mod comment_panel;
+mod game_navigator;
+mod halfmove_clock;
+mod material_bar;
+mod move_input;
 mod move_list;
-mod moves;
-mod piece;
-mod pos;
-mod take_while;
+mod net;
+mod result_banner;
 
-use crate::board::Board;
-use crate::board::BoardState;
-use crate::board::GameResult;
-use crate::board::Square;
-use crate::moves::internal::Move;
-use crate::moves::san;
-use crate::moves::CastlingSide;
-use crate::piece::{Piece, PieceColor, PieceType};
-use crate::pos::{File, Pos, Rank};
+use crate::board_view::Board;
 
 #[derive(Debug, PartialEq)]
 pub enum Msg {
     ClickOnSquare(Pos),
     AddMoveToMovelist(san::Move),
+    NewGame,
+    /// Starts a new game, asking first if one is in progress.
+    RequestNewGame,
+    SetBoardTheme(BoardTheme),
+    SetOrientation(BoardOrientation),
+    SetHighlightStyle(HighlightStyle),
+    /// Whether to mark the squares the selected piece can move to.
+    SetShowLegalMoves(bool),
+    /// Whether to mark the square a pawn can be taken en passant on.
+    SetShowEnPassant(bool),
+    /// Whether clicking another own piece while one is selected selects it instead.
+    SetStickySelection(bool),
+    /// Whether to play a sound for each move.
+    SetSounds(bool),
+    /// Promotes to the given piece without asking, `None` to ask every time.
+    SetAutoPromote(Option<PromotedTo>),
+    /// Plays against the computer at the given strength, `None` for two local players.
+    SetDifficulty(Option<Difficulty>),
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    ClaimFiftyMoveDraw,
+    /// Takes back the last move.
+    TakeBack,
+    KeyPressed(String),
+    /// A move typed in SAN or UCI.
+    SubmitMove(String),
+    /// Shows the position after the given number of plies.
+    GoToPly(usize),
+    /// Reads the games of a PGN collection into the game navigator.
+    LoadGames(String),
+    /// Replays the game of the loaded collection with the given index.
+    SelectGame(usize),
+    /// Sets the comment on the viewed move.
+    EditComment(String),
+    /// Right button pressed on a square, starting an arrow there.
+    StartArrow(Pos),
+    /// Draws an arrow of the given color from one square to another.
+    AddArrow(Pos, Pos, String),
+    ClearArrows,
+    /// Debug builds: plays a move typed in SAN and logs the resulting FEN.
+    #[cfg(debug_assertions)]
+    DebugSan(String),
+    /// Debug builds: labels the square under the mouse pointer with its name.
+    #[cfg(debug_assertions)]
+    SetShowCoordinates(bool),
+    /// The mouse pointer moved to a square, or left the board.
+    HoverSquare(Option<Pos>),
+    Net(net::NetMessage),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Moves {
-    pub inner: Rc<RefCell<Vec<san::Move>>>,
+    pub inner: Rc<RefCell<Vec<AnnotatedMove>>>,
+    /// The number of the first move and the side making it.
+    pub start: Rc<Cell<(MoveNumber, PieceColor)>>,
 }
 
 impl Moves {
     pub fn new() -> Self {
         Self {
             inner: Default::default(),
+            start: Rc::new(Cell::new((1, PieceColor::White))),
+        }
+    }
+
+    /// Numbers the moves from the given starting position on.
+    pub fn set_start(&self, state: &BoardState) {
+        self.start.set((state.move_number(), state.side_to_move()));
+    }
+
+    pub fn push(&self, san: san::Move) {
+        self.inner
+            .borrow_mut()
+            .push(AnnotatedMove { san, comment: None });
+    }
+
+    pub fn pop(&self) -> Option<AnnotatedMove> {
+        self.inner.borrow_mut().pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.borrow().len()
+    }
+
+    /// Attaches a comment to the move leading to the position after `ply` plies.
+    pub fn set_comment(&self, ply: usize, comment: String) {
+        let mut moves = self.inner.borrow_mut();
+        if let Some(mv) = ply.checked_sub(1).and_then(|i| moves.get_mut(i)) {
+            mv.comment = (!comment.is_empty()).then_some(comment);
         }
     }
 
-    pub fn push(&self, mv: san::Move) {
-        self.inner.borrow_mut().push(mv);
+    pub fn clear(&self) {
+        self.inner.borrow_mut().clear();
     }
 }
 
@@ -65,3 +143,18 @@ fn model() -> Html {
 fn main() {
     yew::start_app::<Model>();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clearing_shared_moves() {
+        let moves = Moves::new();
+        let shared = moves.clone();
+        moves.push("e4".parse().unwrap());
+        assert_eq!(shared.len(), 1);
+        shared.clear();
+        assert_eq!(moves.len(), 0);
+    }
+}
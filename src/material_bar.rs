@@ -0,0 +1,54 @@
+use yew::prelude::*;
+
+/// Material advantage in whole pawns, from White's point of view: `+3`, `-9`, or `=`
+/// when neither side is up at least a pawn.
+pub fn format_advantage(balance: i32) -> String {
+    match balance / 100 {
+        0 => "=".to_string(),
+        pawns if pawns > 0 => format!("+{pawns}"),
+        pawns => pawns.to_string(),
+    }
+}
+
+/// Width of White's part of the bar in percent. Ten pawns or more fill it entirely.
+pub fn white_share(balance: i32) -> u8 {
+    (50 + balance.clamp(-1000, 1000) / 20) as u8
+}
+
+#[derive(Debug, PartialEq, Properties)]
+pub struct Props {
+    /// White's material minus Black's, in centipawns.
+    pub balance: i32,
+}
+
+#[function_component(MaterialBar)]
+pub fn material_bar(props: &Props) -> Html {
+    let white = format!("width:{}%", white_share(props.balance));
+    html! {
+        <div class={classes!("flex", "flex-row", "items-center", "gap-2", "p-2")}>
+            <span title="Material advantage, in pawns">
+                { format_advantage(props.balance) }
+            </span>
+            <div class={classes!("flex", "flex-row", "w-32", "h-2", "bg-gray-800")}>
+                <div class={classes!("bg-gray-100")} style={white}></div>
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advantage() {
+        assert_eq!(format_advantage(0), "=");
+        assert_eq!(format_advantage(300), "+3");
+        assert_eq!(format_advantage(-900), "-9");
+        assert_eq!(format_advantage(320), "+3");
+        assert_eq!(format_advantage(-80), "=");
+        assert_eq!(white_share(0), 50);
+        assert_eq!(white_share(500), 75);
+        assert_eq!(white_share(-3900), 0);
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+
+use chess::board::BoardState;
+use chess::moves::internal::Move;
+use chess::moves::san::{FromPos, Move as SanMove};
+use chess::pos::Pos;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Why a typed move can't be played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveParseError {
+    /// Neither SAN nor UCI.
+    Syntax(String),
+    Illegal(String),
+    /// SAN matching several legal moves, like `Nd2` with knights on b1 and f3.
+    Ambiguous(String),
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(input) => write!(f, "{input:?} is not a move, try Nf3 or g1f3"),
+            Self::Illegal(input) => write!(f, "{input} is not a legal move"),
+            Self::Ambiguous(input) => write!(f, "{input} is ambiguous, add the file or rank"),
+        }
+    }
+}
+
+/// Parses a move of the side to move typed in SAN (`Nf3`) or, failing that, UCI (`g1f3`).
+pub fn parse_user_move(input: &str, state: &BoardState) -> Result<Move, MoveParseError> {
+    let input = input.trim();
+    let is_san = match resolve_san(input, state) {
+        Ok(mv) => return Ok(mv),
+        Err(err @ MoveParseError::Ambiguous(_)) => return Err(err),
+        Err(err) => matches!(err, MoveParseError::Illegal(_)),
+    };
+    if let Some(mv) = state.from_uci(input) {
+        return Ok(mv);
+    }
+    let looks_like_uci = input.len() >= 4
+        && input.is_char_boundary(4)
+        && input[0..2].parse::<Pos>().is_ok()
+        && input[2..4].parse::<Pos>().is_ok();
+    if is_san || looks_like_uci {
+        Err(MoveParseError::Illegal(input.to_string()))
+    } else {
+        Err(MoveParseError::Syntax(input.to_string()))
+    }
+}
+
+/// Resolves a move of the side to move typed in SAN against its legal moves.
+pub fn resolve_san(input: &str, state: &BoardState) -> Result<Move, MoveParseError> {
+    let input = input.trim();
+    let san = input
+        .parse::<SanMove>()
+        .map_err(|_| MoveParseError::Syntax(input.to_string()))?;
+    let candidates: Vec<Move> = state
+        .all_legal_moves()
+        .into_iter()
+        .filter(|mv| san_matches(state, *mv, san))
+        .collect();
+    match candidates.as_slice() {
+        [mv] => Ok(*mv),
+        [] => Err(MoveParseError::Illegal(input.to_string())),
+        _ => Err(MoveParseError::Ambiguous(input.to_string())),
+    }
+}
+
+/// Whether the legal `mv` is what `san` means. Unlike the SAN of `mv`, the typed one
+/// may specify the origin even if it's not needed.
+fn san_matches(state: &BoardState, mv: Move, san: SanMove) -> bool {
+    let from = mv.from();
+    match (san, state.to_san_move(mv)) {
+        (SanMove::Castling { side }, SanMove::Castling { side: other }) => side == other,
+        (
+            SanMove::Piece {
+                piece,
+                from: hint,
+                to,
+                ..
+            },
+            SanMove::Piece {
+                piece: other_piece,
+                to: other_to,
+                ..
+            },
+        ) => {
+            let from_matches = match (hint, from) {
+                (None, _) => true,
+                (Some(FromPos::Square(square)), Some(from)) => square == from,
+                (Some(FromPos::File(file)), Some(from)) => file == from.file,
+                (Some(FromPos::Rank(rank)), Some(from)) => rank == from.rank,
+                (Some(_), None) => false,
+            };
+            piece == other_piece && to == other_to && from_matches
+        }
+        (
+            SanMove::PawnPush { to, promoted },
+            SanMove::PawnPush {
+                to: other_to,
+                promoted: other_promoted,
+            },
+        ) => to == other_to && promoted == other_promoted,
+        (
+            SanMove::PawnCapture {
+                from_file,
+                from_rank,
+                to,
+                promoted,
+            },
+            SanMove::PawnCapture {
+                from_file: other_file,
+                to: other_to,
+                promoted: other_promoted,
+                ..
+            },
+        ) => {
+            let rank_matches =
+                from_rank.map_or(true, |rank| from.map(|from| from.rank) == Some(rank));
+            from_file == other_file && to == other_to && promoted == other_promoted && rank_matches
+        }
+        #[cfg(feature = "crazyhouse")]
+        (
+            SanMove::Drop { piece, to },
+            SanMove::Drop {
+                piece: other_piece,
+                to: other_to,
+            },
+        ) => piece == other_piece && to == other_to,
+        _ => false,
+    }
+}
+
+#[derive(Debug, PartialEq, Properties)]
+pub struct Props {
+    /// Why the last submitted move was rejected.
+    pub error: Option<String>,
+    pub on_submit: Callback<String>,
+}
+
+#[function_component(MoveInput)]
+pub fn move_input(props: &Props) -> Html {
+    let input = use_node_ref();
+    let onsubmit = {
+        let input = input.clone();
+        let on_submit = props.on_submit.clone();
+        Callback::from(move |event: FocusEvent| {
+            event.prevent_default();
+            if let Some(input) = input.cast::<HtmlInputElement>() {
+                on_submit.emit(input.value());
+                input.set_value("");
+            }
+        })
+    };
+    let error = props.error.as_ref().map(|error| {
+        html! {
+            <span class={classes!("text-red-600")}>{ error }</span>
+        }
+    });
+    html! {
+        <form {onsubmit} class={classes!("flex", "flex-row", "items-center", "gap-2", "p-2")}>
+            <input ref={input} type="text" placeholder="Move, e.g. Nf3 or g1f3"/>
+            <button type="submit">{ "Play" }</button>
+            { for error }
+        </form>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn san_and_uci() {
+        let state = BoardState::new();
+        let nf3 = state.from_uci("g1f3").unwrap();
+        assert_eq!(parse_user_move("Nf3", &state), Ok(nf3));
+        assert_eq!(parse_user_move("Ngf3", &state), Ok(nf3));
+        assert_eq!(parse_user_move(" g1f3 ", &state), Ok(nf3));
+        let e4 = state.from_uci("e2e4").unwrap();
+        assert_eq!(parse_user_move("e4", &state), Ok(e4));
+    }
+
+    #[test]
+    fn san_only() {
+        let mut state = BoardState::new();
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bb5"] {
+            let mv = resolve_san(san, &state).unwrap();
+            state.make_move(mv);
+        }
+        assert_eq!(
+            state.to_fen(),
+            "r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3"
+        );
+        assert_eq!(
+            resolve_san("g1f3", &BoardState::new()),
+            Err(MoveParseError::Syntax("g1f3".to_string()))
+        );
+        assert_eq!(
+            resolve_san("Qh5", &BoardState::new()),
+            Err(MoveParseError::Illegal("Qh5".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejected_moves() {
+        let state = BoardState::from_fen("k7/8/8/8/8/8/8/1N3N1K w - - 0 1").unwrap();
+        assert_eq!(
+            parse_user_move("Nd2", &state),
+            Err(MoveParseError::Ambiguous("Nd2".to_string()))
+        );
+        assert!(parse_user_move("Nbd2", &state).is_ok());
+        assert_eq!(
+            parse_user_move("Nd4", &state),
+            Err(MoveParseError::Illegal("Nd4".to_string()))
+        );
+        assert_eq!(
+            parse_user_move("h1h3", &state),
+            Err(MoveParseError::Illegal("h1h3".to_string()))
+        );
+        assert_eq!(
+            parse_user_move("hello", &state),
+            Err(MoveParseError::Syntax("hello".to_string()))
+        );
+    }
+}
@@ -1,8 +1,58 @@
+use chess::board::BoardState;
+use chess::moves::{san, MoveNumber};
+use chess::piece::PieceColor;
 use yew::prelude::*;
 
-use crate::board::BoardState;
-use crate::moves::san::Move;
-use crate::Msg;
+/// A played move with the comment the user attached to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedMove {
+    pub san: san::Move,
+    pub comment: Option<String>,
+}
+
+/// The comment on the move that led to the position after `ply` plies.
+pub fn comment_at(moves: &[AnnotatedMove], ply: usize) -> Option<&str> {
+    let index = ply.checked_sub(1)?;
+    moves.get(index)?.comment.as_deref()
+}
+
+/// The position after the first `ply` moves of the game started from `start`.
+pub fn state_at_ply(start: &BoardState, moves: &[AnnotatedMove], ply: usize) -> BoardState {
+    let mut state = start.clone();
+    for mv in moves.iter().take(ply) {
+        let mv = match state.from_san_move(mv.san) {
+            Ok(mv) => mv,
+            Err(_) => break,
+        };
+        state.make_move(mv);
+    }
+    state
+}
+
+/// A row of the move list: the move number, White's move and Black's reply if any.
+pub fn format_row(number: usize, white: &AnnotatedMove, black: Option<&AnnotatedMove>) -> String {
+    match black {
+        Some(black) => format!("{number}. {} {}", white.san, black.san),
+        None => format!("{number}. {}", white.san),
+    }
+}
+
+/// The rows of the move list of a game whose first move has the given number and is
+/// made by `first`. If that's Black, the first row only holds Black's move, like `12... Kd7`.
+pub fn format_rows(moves: &[AnnotatedMove], number: MoveNumber, first: PieceColor) -> Vec<String> {
+    let mut number = number as usize;
+    let mut rows = Vec::new();
+    let mut rest = moves;
+    if let (PieceColor::Black, [black, tail @ ..]) = (first, moves) {
+        rows.push(format!("{number}... {}", black.san));
+        number += 1;
+        rest = tail;
+    }
+    for (i, row) in rest.chunks(2).enumerate() {
+        rows.push(format_row(number + i, &row[0], row.get(1)));
+    }
+    rows
+}
 
 #[derive(Debug, PartialEq, Properties)]
 pub struct Props {
@@ -12,13 +62,10 @@ pub struct Props {
 #[function_component(MoveList)]
 pub fn move_list(props: &Props) -> Html {
     let moves = props.move_list.inner.borrow();
-    let rows = moves.chunks(2).enumerate().map(|(i, r)| {
-        let first = r.get(0).map(ToString::to_string).unwrap();
-        let second = r.get(1).map(ToString::to_string).unwrap_or_default();
+    let (number, first) = props.move_list.start.get();
+    let rows = format_rows(&moves, number, first).into_iter().map(|row| {
         html! {
-            <div>
-                { format!("{i}. {first} {second}") }
-            </div>
+            <div>{ row }</div>
         }
     });
     html! {
@@ -27,3 +74,98 @@ pub fn move_list(props: &Props) -> Html {
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn promotions_in_rows() {
+        use chess::moves::PromotedTo;
+        use chess::pos::{File, Pos, Rank};
+        let annotated = |san: san::Move| AnnotatedMove { san, comment: None };
+        let e8 = Pos::new(File::E, Rank::new(8));
+        let d1 = Pos::new(File::D, Rank::new(1));
+        let queen = annotated(san::Move::PawnPush {
+            to: e8,
+            promoted: Some(PromotedTo::Queen),
+        });
+        let knight = annotated(san::Move::PawnCapture {
+            from_file: File::E,
+            from_rank: Some(Rank::new(2)),
+            to: d1,
+            promoted: Some(PromotedTo::Knight),
+        });
+        assert_eq!(format_row(42, &queen, Some(&knight)), "42. e8=Q e2xd1=N");
+        assert_eq!(format_row(43, &queen, None), "43. e8=Q");
+        let parsed: san::Move = "exd1=N".parse().unwrap();
+        assert_eq!(parsed.to_string(), "exd1=N");
+    }
+
+    #[test]
+    fn black_moves_first() {
+        let start = BoardState::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap();
+        let moves: Vec<_> = ["Kd7", "e4", "Kc6"]
+            .into_iter()
+            .map(|san| AnnotatedMove {
+                san: san.parse().unwrap(),
+                comment: None,
+            })
+            .collect();
+        let state = state_at_ply(&start, &moves, 1);
+        assert_eq!(state.move_number(), 41);
+        let rows = format_rows(&moves, start.move_number(), start.side_to_move());
+        assert_eq!(rows, ["40... Kd7", "41. e4 Kc6"]);
+        let rows = format_rows(&moves, 1, PieceColor::White);
+        assert_eq!(rows, ["1. Kd7 e4", "2. Kc6"]);
+        assert!(format_rows(&[], 40, PieceColor::Black).is_empty());
+    }
+
+    #[test]
+    fn navigation_turn_and_move_number() {
+        let game = |moves: &[&str]| -> Vec<AnnotatedMove> {
+            moves
+                .iter()
+                .map(|san| AnnotatedMove {
+                    san: san.parse().unwrap(),
+                    comment: None,
+                })
+                .collect()
+        };
+        let moves = game(&["e4", "c5", "Nf3", "d6"]);
+        let state = state_at_ply(&BoardState::new(), &moves, 3);
+        assert_eq!(state.turn(), PieceColor::Black);
+        assert_eq!(state.move_number(), 2);
+        let state = state_at_ply(&BoardState::new(), &moves, 4);
+        assert_eq!(state.turn(), PieceColor::White);
+        assert_eq!(state.move_number(), 3);
+        // A game from a position with Black to move.
+        let start = BoardState::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap();
+        let state = state_at_ply(&start, &game(&["Kd7", "e4", "Kc6"]), 3);
+        assert_eq!(state.turn(), PieceColor::White);
+        assert_eq!(state.move_number(), 42);
+    }
+
+    #[test]
+    fn comments_by_ply() {
+        let annotated = |san: &str, comment: Option<&str>| AnnotatedMove {
+            san: san.parse().unwrap(),
+            comment: comment.map(ToString::to_string),
+        };
+        let moves = [
+            annotated("e4", Some("Best by test")),
+            annotated("c5", None),
+            annotated("Nf3", Some("Open Sicilian")),
+        ];
+        assert_eq!(comment_at(&moves, 0), None);
+        assert_eq!(comment_at(&moves, 1), Some("Best by test"));
+        assert_eq!(comment_at(&moves, 2), None);
+        assert_eq!(comment_at(&moves, 3), Some("Open Sicilian"));
+        assert_eq!(comment_at(&moves, 4), None);
+        let state = state_at_ply(&BoardState::new(), &moves, 2);
+        assert_eq!(
+            state.to_fen(),
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"
+        );
+    }
+}
@@ -7,12 +7,26 @@ pub type Ply = u32;
 pub type MoveNumber = u32;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CastlingSide {
     Short,
     Long,
 }
 
+/// What happened on the board after a move, used for feedback like sounds.
+/// If several apply, the most significant one is reported.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveEvent {
+    Move,
+    Capture,
+    Castle,
+    Check,
+    Promotion,
+    GameEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PromotedTo {
     Knight,
     Bishop,
@@ -34,7 +48,7 @@ impl PromotedTo {
 impl fmt::Display for PromotedTo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Knight => write!(f, "=K"),
+            Self::Knight => write!(f, "=N"),
             Self::Bishop => write!(f, "=B"),
             Self::Rook => write!(f, "=R"),
             Self::Queen => write!(f, "=Q"),
@@ -45,6 +59,8 @@ impl fmt::Display for PromotedTo {
 pub mod internal {
     use super::CastlingSide;
     use super::PromotedTo;
+    #[cfg(feature = "crazyhouse")]
+    use crate::piece::PieceType;
     use crate::pos::Pos;
     use std::fmt;
 
@@ -58,6 +74,12 @@ pub mod internal {
         Castling {
             side: CastlingSide,
         },
+        /// Crazyhouse: a piece from the hand put on an empty square.
+        #[cfg(feature = "crazyhouse")]
+        Drop {
+            piece: PieceType,
+            to: Pos,
+        },
     }
 
     impl Move {
@@ -79,10 +101,31 @@ pub mod internal {
             Self::Castling { side }
         }
 
+        /// The same promotion with another piece chosen. Moves that don't promote are
+        /// returned unchanged: promotions to different pieces are different moves, even
+        /// though they share `from` and `to`.
+        pub fn with_promotion(self, piece: PromotedTo) -> Self {
+            match self {
+                Self::Regular { from, to, promoted: Some(_) } => {
+                    Self::new_with_promoted(from, to, Some(piece))
+                }
+                other => other,
+            }
+        }
+
+        pub fn promoted(&self) -> Option<PromotedTo> {
+            match self {
+                Self::Regular { promoted, .. } => *promoted,
+                _ => None,
+            }
+        }
+
         pub fn from(&self) -> Option<Pos> {
             match self {
                 Self::Regular { from, .. } => Some(*from),
                 Self::Castling { .. } => None,
+                #[cfg(feature = "crazyhouse")]
+                Self::Drop { .. } => None,
             }
         }
 
@@ -90,6 +133,8 @@ pub mod internal {
             match self {
                 Self::Regular { to, .. } => Some(*to),
                 Self::Castling { .. } => None,
+                #[cfg(feature = "crazyhouse")]
+                Self::Drop { to, .. } => Some(*to),
             }
         }
     }
@@ -108,8 +153,75 @@ pub mod internal {
                 Self::Castling {
                     side: CastlingSide::Long,
                 } => write!(f, "O-O-O"),
+                #[cfg(feature = "crazyhouse")]
+                Self::Drop { piece, to } => write!(f, "{piece}@{to}"),
+            }
+        }
+    }
+
+    /// UCI-like notation: `"e2e4"`, `"e7e8q"`. Castling is stored as
+    /// `"O-O"` or `"O-O-O"`, because the move itself doesn't know the side.
+    impl Move {
+        pub fn to_compact(&self) -> String {
+            match self {
+                Self::Regular { from, to, promoted } => {
+                    let promoted = match promoted {
+                        Some(PromotedTo::Queen) => "q",
+                        Some(PromotedTo::Rook) => "r",
+                        Some(PromotedTo::Bishop) => "b",
+                        Some(PromotedTo::Knight) => "n",
+                        None => "",
+                    };
+                    format!("{from}{to}{promoted}")
+                }
+                Self::Castling { .. } => self.to_string(),
+                #[cfg(feature = "crazyhouse")]
+                Self::Drop { .. } => self.to_string(),
             }
         }
+
+        pub fn from_compact(s: &str) -> Option<Self> {
+            match s {
+                "O-O" => return Some(Self::castling(CastlingSide::Short)),
+                "O-O-O" => return Some(Self::castling(CastlingSide::Long)),
+                _ => {}
+            }
+            #[cfg(feature = "crazyhouse")]
+            if let Some((piece, to)) = s.split_once('@') {
+                let piece = match piece.as_bytes() {
+                    [c] => PieceType::from_char(*c as char)?,
+                    _ => return None,
+                };
+                return Some(Self::Drop { piece, to: to.parse().ok()? });
+            }
+            let from = s.get(0..2)?.parse().ok()?;
+            let to = s.get(2..4)?.parse().ok()?;
+            let promoted = match s.get(4..)? {
+                "" => None,
+                "q" => Some(PromotedTo::Queen),
+                "r" => Some(PromotedTo::Rook),
+                "b" => Some(PromotedTo::Bishop),
+                "n" => Some(PromotedTo::Knight),
+                _ => return None,
+            };
+            Some(Self::new_with_promoted(from, to, promoted))
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl serde::Serialize for Move {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.to_compact())
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de> serde::Deserialize<'de> for Move {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+            Self::from_compact(&s)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid move {s:?}")))
+        }
     }
 }
 
@@ -121,8 +233,10 @@ pub mod san {
     use crate::pos::Pos;
     use crate::pos::Rank;
     use std::fmt;
+    use std::str::FromStr;
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub enum FromPos {
         Square(Pos),
         File(File),
@@ -140,6 +254,7 @@ pub mod san {
     }
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub enum Move {
         Piece {
             piece: PieceType,
@@ -160,6 +275,12 @@ pub mod san {
         Castling {
             side: CastlingSide,
         },
+        /// Crazyhouse drop, like `N@f3`.
+        #[cfg(feature = "crazyhouse")]
+        Drop {
+            piece: PieceType,
+            to: Pos,
+        },
     }
 
     impl fmt::Display for Move {
@@ -192,11 +313,7 @@ pub mod san {
                 } => write!(
                     f,
                     "{from_file}{from_rank}x{to}{promoted}",
-                    from_rank = from_rank
-                        .map(|r| r.get().clone())
-                        .as_ref()
-                        .map(ToString::to_string)
-                        .unwrap_or_default(),
+                    from_rank = from_rank.map(|r| r.get().to_string()).unwrap_or_default(),
                     promoted = promoted
                         .as_ref()
                         .map(ToString::to_string)
@@ -208,7 +325,201 @@ pub mod san {
                 Self::Castling {
                     side: CastlingSide::Long,
                 } => write!(f, "O-O-O"),
+                #[cfg(feature = "crazyhouse")]
+                Self::Drop { piece, to } => write!(f, "{piece}@{to}"),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseSanError(pub String);
+
+    impl fmt::Display for ParseSanError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "invalid SAN move {:?}", self.0)
+        }
+    }
+
+    impl FromStr for Move {
+        type Err = ParseSanError;
+
+        /// Parses moves like `Nbd7`, `exd8=Q` or `O-O`. Trailing check marks
+        /// and annotations like `+`, `#` or `!?` are ignored, so a wrong check
+        /// mark, which PGN files sometimes have, still parses.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let err = || ParseSanError(s.to_string());
+            let san = s.trim_end_matches(['+', '#', '!', '?']);
+            match san {
+                "O-O" | "0-0" => return Ok(Self::Castling { side: CastlingSide::Short }),
+                "O-O-O" | "0-0-0" => return Ok(Self::Castling { side: CastlingSide::Long }),
+                _ => {}
+            }
+            if !san.is_ascii() {
+                return Err(err());
+            }
+            #[cfg(feature = "crazyhouse")]
+            if let Some((piece, to)) = san.split_once('@') {
+                let piece = match piece.as_bytes() {
+                    [c] => PieceType::from_char(*c as char).ok_or_else(err)?,
+                    _ => return Err(err()),
+                };
+                let to = to.parse().map_err(|_| err())?;
+                return Ok(Self::Drop { piece, to });
+            }
+
+            let (san, promoted) = match san.len().checked_sub(2).map(|i| san.split_at(i)) {
+                Some((rest, promotion)) if promotion.starts_with('=') => {
+                    let promoted = match &promotion[1..] {
+                        "Q" => PromotedTo::Queen,
+                        "R" => PromotedTo::Rook,
+                        "B" => PromotedTo::Bishop,
+                        "N" => PromotedTo::Knight,
+                        _ => return Err(err()),
+                    };
+                    (rest, Some(promoted))
+                }
+                _ => (san, None),
+            };
+            let split = san.len().checked_sub(2).ok_or_else(err)?;
+            let (rest, to) = san.split_at(split);
+            let to: Pos = to.parse().map_err(|_| err())?;
+            let (rest, is_capture) = match rest.strip_suffix('x') {
+                Some(rest) => (rest, true),
+                None => (rest, false),
+            };
+
+            let mut chars = rest.chars();
+            let piece = match chars.next() {
+                Some('K') => PieceType::King,
+                Some('Q') => PieceType::Queen,
+                Some('R') => PieceType::Rook,
+                Some('B') => PieceType::Bishop,
+                Some('N') => PieceType::Knight,
+                None if !is_capture => return Ok(Self::PawnPush { to, promoted }),
+                Some(file) if is_capture => {
+                    let from_file = File::from_char(file).ok_or_else(err)?;
+                    let from_rank = match chars.next() {
+                        Some(rank) => Some(parse_rank(rank).ok_or_else(err)?),
+                        None => None,
+                    };
+                    if chars.next().is_some() {
+                        return Err(err());
+                    }
+                    return Ok(Self::PawnCapture { from_file, from_rank, to, promoted });
+                }
+                _ => return Err(err()),
+            };
+            if promoted.is_some() {
+                return Err(err());
             }
+            let from = match chars.as_str() {
+                "" => None,
+                from if from.len() == 2 => Some(FromPos::Square(from.parse().map_err(|_| err())?)),
+                from => {
+                    let c = from.chars().next().filter(|_| from.len() == 1).ok_or_else(err)?;
+                    match (File::from_char(c), parse_rank(c)) {
+                        (Some(file), _) => Some(FromPos::File(file)),
+                        (_, Some(rank)) => Some(FromPos::Rank(rank)),
+                        _ => return Err(err()),
+                    }
+                }
+            };
+            Ok(Self::Piece { piece, is_capture, from, to })
         }
     }
+
+    fn parse_rank(c: char) -> Option<Rank> {
+        c.to_digit(10).and_then(|r| Rank::try_new(r as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pos::{File, Pos, Rank};
+    #[cfg(feature = "serde")]
+    use serde::de::DeserializeOwned;
+    #[cfg(feature = "serde")]
+    use serde::Serialize;
+
+    #[test]
+    fn pawn_capture_san() {
+        let capture = |from_rank: Option<u8>, to: &str, promoted| san::Move::PawnCapture {
+            from_file: File::E,
+            from_rank: from_rank.map(Rank::new),
+            to: to.parse::<Pos>().unwrap(),
+            promoted,
+        };
+        for (mv, expected) in [
+            (capture(None, "d5", None), "exd5"),
+            (capture(Some(5), "d6", None), "e5xd6"),
+            (capture(None, "d8", Some(PromotedTo::Queen)), "exd8=Q"),
+        ] {
+            assert_eq!(mv.to_string(), expected);
+            assert_eq!(expected.parse::<san::Move>().unwrap(), mv);
+        }
+    }
+
+    #[test]
+    fn check_marks() {
+        use crate::board::BoardState;
+        let play = |moves: &[&str]| {
+            let mut state = BoardState::new();
+            for mv in moves {
+                state.make_move(state.from_san_move(mv.parse().unwrap()).unwrap());
+            }
+            state
+        };
+        let mate: san::Move = "Qxf7#".parse().unwrap();
+        assert_eq!(mate, "Qxf7".parse().unwrap());
+        let state = play(&["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6"]);
+        let mv = state.from_san_move(mate).unwrap();
+        assert_eq!(state.to_uci(mv), "h5f7");
+        // Bb5 isn't check here, the mark is ignored anyway.
+        let pin: san::Move = "Bb5+".parse().unwrap();
+        assert_eq!(pin, "Bb5".parse().unwrap());
+        let state = play(&["e4", "e5", "Nf3", "Nc6"]);
+        let mv = state.from_san_move(pin).unwrap();
+        assert_eq!(state.to_uci(mv), "f1b5");
+        assert!(!state.move_gives_check(mv));
+    }
+
+    #[cfg(feature = "serde")]
+    fn roundtrip<T: Serialize + DeserializeOwned + PartialEq + fmt::Debug>(value: T) -> String {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<T>(&json).unwrap(), value);
+        json
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let e2 = Pos::new(File::E, Rank::new(2));
+        let e4 = Pos::new(File::E, Rank::new(4));
+        assert_eq!(roundtrip(e4), r#""e4""#);
+        roundtrip(File::H);
+        roundtrip(Rank::new(8));
+        assert!(serde_json::from_str::<Rank>("9").is_err());
+        roundtrip(PieceColor::Black);
+        roundtrip(PieceType::Knight);
+        roundtrip(Piece { kind: PieceType::Queen, color: PieceColor::White });
+        roundtrip(CastlingSide::Long);
+
+        assert_eq!(roundtrip(internal::Move::new(e2, e4)), r#""e2e4""#);
+        let e7 = Pos::new(File::E, Rank::new(7));
+        let e8 = Pos::new(File::E, Rank::new(8));
+        let promotion = internal::Move::new_with_promoted(e7, e8, Some(PromotedTo::Knight));
+        assert_eq!(roundtrip(promotion), r#""e7e8n""#);
+        assert_eq!(roundtrip(internal::Move::castling(CastlingSide::Short)), r#""O-O""#);
+        assert!(serde_json::from_str::<internal::Move>(r#""e2""#).is_err());
+
+        roundtrip(san::Move::PawnPush { to: e4, promoted: None });
+        roundtrip(san::Move::Piece {
+            piece: PieceType::Knight,
+            is_capture: true,
+            from: Some(san::FromPos::File(File::B)),
+            to: Pos::new(File::D, Rank::new(2)),
+        });
+        roundtrip(san::Move::Castling { side: CastlingSide::Short });
+    }
 }
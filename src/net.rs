@@ -0,0 +1,136 @@
+//! Two-player games over a relay WebSocket server.
+//!
+//! The relay forwards every message to the other player of the same game.
+//! Moves are sent in UCI notation.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{MessageEvent, WebSocket};
+use yew::Callback;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NetMessage {
+    JoinGame(String),
+    /// Sent by the relay after joining, tells which side the receiver plays.
+    AssignColor {
+        white: bool,
+    },
+    MakeMove(String),
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    ClaimFiftyMoveDraw,
+}
+
+impl NetMessage {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("NetMessage is always serializable")
+    }
+
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str(json).ok()
+    }
+}
+
+pub struct NetClient {
+    socket: WebSocket,
+    _on_open: Closure<dyn FnMut()>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl NetClient {
+    /// Connects to the relay at `url` and joins `game_id` as soon as the connection is open.
+    pub fn connect(
+        url: &str,
+        game_id: String,
+        on_message: Callback<NetMessage>,
+    ) -> Result<Self, JsValue> {
+        let socket = WebSocket::new(url)?;
+        let on_open = {
+            let socket = socket.clone();
+            Closure::wrap(Box::new(move || {
+                let join = NetMessage::JoinGame(game_id.clone()).to_json();
+                if socket.send_with_str(&join).is_err() {
+                    gloo::console::error!("Failed to join game");
+                }
+            }) as Box<dyn FnMut()>)
+        };
+        let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
+            let msg = event
+                .data()
+                .as_string()
+                .and_then(|s| NetMessage::from_json(&s));
+            match msg {
+                Some(msg) => on_message.emit(msg),
+                None => gloo::console::error!("Unexpected message from relay"),
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        Ok(Self {
+            socket,
+            _on_open: on_open,
+            _on_message: on_message,
+        })
+    }
+
+    pub fn send(&self, msg: &NetMessage) {
+        if self.socket.send_with_str(&msg.to_json()).is_err() {
+            gloo::console::error!("Failed to send message to relay");
+        }
+    }
+}
+
+impl Drop for NetClient {
+    fn drop(&mut self) {
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        let _ = self.socket.close();
+    }
+}
+
+/// Looks up `key` in a URL query string like `?relay=wss://example.com&game=42`.
+pub fn query_param(search: &str, key: &str) -> Option<String> {
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_roundtrip() {
+        let messages = [
+            NetMessage::JoinGame("42".to_string()),
+            NetMessage::AssignColor { white: false },
+            NetMessage::MakeMove("e7e8q".to_string()),
+            NetMessage::Resign,
+            NetMessage::OfferDraw,
+            NetMessage::AcceptDraw,
+            NetMessage::DeclineDraw,
+            NetMessage::ClaimFiftyMoveDraw,
+        ];
+        for msg in messages {
+            assert_eq!(NetMessage::from_json(&msg.to_json()), Some(msg));
+        }
+        assert_eq!(NetMessage::from_json("{\"Castle\":null}"), None);
+    }
+
+    #[test]
+    fn query() {
+        let search = "?relay=ws://localhost:9001&game=42";
+        assert_eq!(
+            query_param(search, "relay").as_deref(),
+            Some("ws://localhost:9001")
+        );
+        assert_eq!(query_param(search, "game").as_deref(), Some("42"));
+        assert_eq!(query_param(search, "color"), None);
+    }
+}
@@ -0,0 +1,423 @@
+//! Reading and writing games in Portable Game Notation.
+
+use crate::board::{BoardState, GameResult};
+use crate::fen::FenError;
+use crate::moves::san::Move as SanMove;
+use crate::piece::PieceColor;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The tag pairs of a PGN header.
+///
+/// The seven standard tags always exist (`?` when unknown), others go to
+/// `extra`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+    pub result: String,
+    pub extra: BTreeMap<String, String>,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        Self {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: "????.??.??".to_string(),
+            round: "?".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+            result: "*".to_string(),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+impl PgnTags {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let value = match key {
+            "Event" => &self.event,
+            "Site" => &self.site,
+            "Date" => &self.date,
+            "Round" => &self.round,
+            "White" => &self.white,
+            "Black" => &self.black,
+            "Result" => &self.result,
+            _ => return self.extra.get(key).map(String::as_str),
+        };
+        Some(value)
+    }
+
+    pub fn set(&mut self, key: &str, value: String) {
+        match key {
+            "Event" => self.event = value,
+            "Site" => self.site = value,
+            "Date" => self.date = value,
+            "Round" => self.round = value,
+            "White" => self.white = value,
+            "Black" => self.black = value,
+            "Result" => self.result = value,
+            _ => {
+                self.extra.insert(key.to_string(), value);
+            }
+        }
+    }
+
+    /// Sets the `Result` tag, `*` for a game still in progress.
+    pub fn set_result(&mut self, result: Option<GameResult>) {
+        self.result = result.map_or("*", |result| result.pgn_token()).to_string();
+    }
+
+    /// All tags, the standard ones first in their conventional order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        [
+            ("Event", &self.event),
+            ("Site", &self.site),
+            ("Date", &self.date),
+            ("Round", &self.round),
+            ("White", &self.white),
+            ("Black", &self.black),
+            ("Result", &self.result),
+        ]
+        .into_iter()
+        .chain(self.extra.iter().map(|(k, v)| (k.as_str(), v)))
+        .map(|(k, v)| (k, v.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    /// A header line that isn't a `[Key "Value"]` pair.
+    Tag(String),
+    Move(String),
+    /// The `FEN` tag of a game starting from a set-up position.
+    Fen(FenError),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tag(line) => write!(f, "invalid tag pair: {line}"),
+            Self::Move(mv) => write!(f, "invalid move: {mv}"),
+            Self::Fen(err) => write!(f, "invalid FEN tag: {err}"),
+        }
+    }
+}
+
+/// A game of a PGN collection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub tags: PgnTags,
+    pub moves: Vec<SanMove>,
+    /// `1-0`, `0-1`, `1/2-1/2` or `*`, from the end of the movetext or else the
+    /// `Result` tag.
+    pub result: String,
+}
+
+pub fn from_pgn(pgn: &str) -> Result<(PgnTags, Vec<SanMove>), PgnError> {
+    let mut tags = PgnTags::default();
+    let mut movetext = String::new();
+    for line in pgn.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            let (key, value) = parse_tag(line).ok_or_else(|| PgnError::Tag(line.to_string()))?;
+            tags.set(&key, value);
+        } else if !line.starts_with('%') {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+    let moves = movetext_tokens(&movetext)
+        .into_iter()
+        .map(|token| token.parse().map_err(|_| PgnError::Move(token)))
+        .collect::<Result<_, _>>()?;
+    Ok((tags, moves))
+}
+
+/// The position the game starts from, the `FEN` tag if it has `[SetUp "1"]`.
+pub fn start_position(tags: &PgnTags) -> Result<BoardState, PgnError> {
+    match (tags.get("SetUp"), tags.get("FEN")) {
+        (Some("1"), Some(fen)) => BoardState::from_fen(fen).map_err(PgnError::Fen),
+        _ => Ok(BoardState::new()),
+    }
+}
+
+/// Replays the game, see [`start_position`].
+pub fn apply_pgn(pgn: &str) -> Result<BoardState, PgnError> {
+    let (tags, moves) = from_pgn(pgn)?;
+    replay(&tags, &moves)
+}
+
+/// The position after `moves` played from the game's [`start_position`].
+pub fn replay(tags: &PgnTags, moves: &[SanMove]) -> Result<BoardState, PgnError> {
+    let mut state = start_position(tags)?;
+    for &san in moves {
+        let mv = state
+            .from_san_move(san)
+            .map_err(|_| PgnError::Move(san.to_string()))?;
+        state.make_move(mv);
+    }
+    Ok(state)
+}
+
+/// Reads every game of a file holding several, each starting with its tag pairs. A game
+/// failing to parse or with an illegal move doesn't affect the others.
+pub fn from_pgn_collection(text: &str) -> Vec<Result<Game, PgnError>> {
+    split_games(text)
+        .into_iter()
+        .map(|pgn| {
+            let (tags, moves) = from_pgn(&pgn)?;
+            replay(&tags, &moves)?;
+            let result = pgn
+                .split_whitespace()
+                .last()
+                .filter(|token| is_result(token))
+                .unwrap_or(&tags.result)
+                .to_string();
+            Ok(Game {
+                tags,
+                moves,
+                result,
+            })
+        })
+        .collect()
+}
+
+/// Writes the game, numbering the moves from the game's [`start_position`], which fails
+/// for a malformed `FEN` tag.
+pub fn to_pgn(tags: &PgnTags, moves: &[SanMove]) -> Result<String, PgnError> {
+    let start = start_position(tags)?;
+    let mut number = start.move_number() as usize;
+    let mut pgn = String::new();
+    for (key, value) in tags.iter() {
+        pgn.push_str(&format!("[{key} \"{}\"]\n", escape(value)));
+    }
+    pgn.push('\n');
+    let mut rest = moves;
+    if let (PieceColor::Black, [black, tail @ ..]) = (start.side_to_move(), moves) {
+        pgn.push_str(&format!("{number}... {black} "));
+        number += 1;
+        rest = tail;
+    }
+    for (i, mv) in rest.iter().enumerate() {
+        if i % 2 == 0 {
+            pgn.push_str(&format!("{}. ", number + i / 2));
+        }
+        pgn.push_str(&format!("{mv} "));
+    }
+    pgn.push_str(&tags.result);
+    pgn.push('\n');
+    Ok(pgn)
+}
+
+/// Parses `[Key "Value"]`, unescaping `\"` and `\\` in the value.
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                c @ ('"' | '\\') => unescaped.push(c),
+                _ => return None,
+            },
+            '"' => return None,
+            c => unescaped.push(c),
+        }
+    }
+    Some((key.to_string(), unescaped))
+}
+
+/// Splits a collection where a tag pair follows movetext.
+fn split_games(text: &str) -> Vec<String> {
+    let mut games = Vec::new();
+    let mut game = String::new();
+    let mut in_movetext = false;
+    for line in text.lines() {
+        let is_tag = line.trim_start().starts_with('[');
+        if is_tag && in_movetext {
+            games.push(std::mem::take(&mut game));
+            in_movetext = false;
+        }
+        in_movetext |= !is_tag && !line.trim().is_empty();
+        game.push_str(line);
+        game.push('\n');
+    }
+    if !game.trim().is_empty() {
+        games.push(game);
+    }
+    games
+}
+
+fn is_result(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// SAN moves of the movetext, without move numbers, comments, variations,
+/// numeric annotations and the game result.
+fn movetext_tokens(movetext: &str) -> Vec<String> {
+    let mut cleaned = String::new();
+    let mut depth = 0;
+    let mut in_comment = false;
+    let mut in_line_comment = false;
+    for c in movetext.chars() {
+        match c {
+            '\n' if in_line_comment => in_line_comment = false,
+            _ if in_line_comment => {}
+            '}' if in_comment => in_comment = false,
+            _ if in_comment => {}
+            '{' => in_comment = true,
+            ';' => in_line_comment = true,
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth > 0 => {}
+            c => cleaned.push(c),
+        }
+        if c == '{' || c == '}' || c == '(' || c == ')' {
+            cleaned.push(' ');
+        }
+    }
+    cleaned
+        .split_whitespace()
+        .filter(|token| !is_result(token))
+        .map(strip_move_number)
+        .filter(|token| !token.is_empty() && !token.starts_with('$'))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Strips a leading move number like `12.` or `12...`.
+fn strip_move_number(token: &str) -> &str {
+    let digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    if digits.len() < token.len() && digits.starts_with('.') {
+        digits.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Square;
+    use crate::piece::{Piece, PieceColor, PieceType};
+
+    const GAME: &str = r#"[Event "The \"Immortal\" Game"]
+[Site "London \\ Simpson's Divan"]
+[Date "1851.06.21"]
+[Round "?"]
+[White "Anderssen, Adolf"]
+[Black "Kieseritzky, Lionel"]
+[Result "1-0"]
+[ECO "C33"]
+
+1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ 4. Kf1 b5 5. Bxb5 Nf6 1-0
+"#;
+
+    #[test]
+    fn tags_with_escapes() {
+        let (tags, moves) = from_pgn(GAME).unwrap();
+        assert_eq!(tags.event, r#"The "Immortal" Game"#);
+        assert_eq!(tags.site, r"London \ Simpson's Divan");
+        assert_eq!(tags.result, "1-0");
+        assert_eq!(tags.get("ECO"), Some("C33"));
+        assert_eq!(moves.len(), 10);
+        assert_eq!(moves[5].to_string(), "Qh4");
+        assert_eq!(to_pgn(&tags, &moves).unwrap(), GAME.replace("Qh4+", "Qh4"));
+    }
+
+    #[test]
+    fn movetext_noise() {
+        let pgn = "1. e4 {best by test} e5 $1 (1... c5 2. Nf3) 2. Nf3 ; comment\n2... Nc6 *";
+        let (tags, moves) = from_pgn(pgn).unwrap();
+        assert_eq!(tags, PgnTags::default());
+        let mut tags = tags;
+        tags.set_result(Some(GameResult::DrawByThreefoldRepetition));
+        assert_eq!(tags.result, "1/2-1/2");
+        tags.set_result(None);
+        assert_eq!(tags, PgnTags::default());
+        let moves: Vec<_> = moves.iter().map(ToString::to_string).collect();
+        assert_eq!(moves, ["e4", "e5", "Nf3", "Nc6"]);
+        assert!(from_pgn("[Event \"unterminated]").is_err());
+        assert!(from_pgn("1. e9").is_err());
+    }
+
+    #[test]
+    fn games_collection() {
+        let second = "[White \"Carlsen\"]\n[Black \"Nakamura\"]\n\n1. d4 d5 2. c4 1/2-1/2\n";
+        let games = from_pgn_collection(&format!("{GAME}\n{second}"));
+        assert_eq!(games.len(), 2);
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(first.tags.white, "Anderssen, Adolf");
+        assert_eq!(first.moves.len(), 10);
+        assert_eq!(first.result, "1-0");
+        let second = games[1].as_ref().unwrap();
+        assert_eq!(second.tags.black, "Nakamura");
+        assert_eq!(second.moves.len(), 3);
+        // Only the movetext has the result.
+        assert_eq!(second.tags.result, "*");
+        assert_eq!(second.result, "1/2-1/2");
+
+        let text = format!(
+            "{GAME}[Event \"Broken\"]\n\n1. e4 e9 *\n[Event \"Illegal\"]\n1. e4 e5 2. Ke3 *\n[Event \"Last\"]\n1. c4"
+        );
+        let games = from_pgn_collection(&text);
+        assert_eq!(games.len(), 4);
+        assert!(games[0].is_ok());
+        assert_eq!(games[1], Err(PgnError::Move("e9".to_string())));
+        assert_eq!(games[2], Err(PgnError::Move("Ke3".to_string())));
+        assert_eq!(games[3].as_ref().unwrap().result, "*");
+        assert!(from_pgn_collection("").is_empty());
+        assert!(from_pgn_collection("\n\n").is_empty());
+    }
+
+    #[test]
+    fn setup_position() {
+        let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 10"]
+
+10... Kd7 11. e4 Kc6 *"#;
+        let state = apply_pgn(pgn).unwrap();
+        assert_eq!(state.move_number(), 12);
+        assert_eq!(state.turn, PieceColor::White);
+        let pawn = Piece {
+            kind: PieceType::Pawn,
+            color: PieceColor::White,
+        };
+        let king = Piece {
+            kind: PieceType::King,
+            color: PieceColor::Black,
+        };
+        assert_eq!(
+            state.square_by_pos("e4".parse().unwrap()),
+            Square::Piece(pawn)
+        );
+        assert_eq!(
+            state.square_by_pos("c6".parse().unwrap()),
+            Square::Piece(king)
+        );
+
+        // Written back with the numbers of the position, and read again the same.
+        let (tags, moves) = from_pgn(pgn).unwrap();
+        let written = to_pgn(&tags, &moves).unwrap();
+        assert!(written.ends_with("\n10... Kd7 11. e4 Kc6 *\n"), "{written}");
+        assert_eq!(from_pgn(&written).unwrap(), (tags, moves));
+        assert_eq!(apply_pgn(&written).unwrap().to_fen(), state.to_fen());
+
+        let bad_fen = "[SetUp \"1\"]\n[FEN \"8/8 w - -\"]\n\n*";
+        assert!(matches!(apply_pgn(bad_fen), Err(PgnError::Fen(_))));
+        let (tags, moves) = from_pgn(bad_fen).unwrap();
+        assert!(matches!(to_pgn(&tags, &moves), Err(PgnError::Fen(_))));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::pos::{File, Pos, Rank};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceColor {
     White,
     Black,
@@ -39,6 +40,7 @@ impl std::ops::Not for PieceColor {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn,
     King,
@@ -48,12 +50,87 @@ pub enum PieceType {
     Knight,
 }
 
+impl PieceType {
+    /// Parses the uppercase letter used in SAN, including `P` for pawns.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'P' => Some(Self::Pawn),
+            'K' => Some(Self::King),
+            'Q' => Some(Self::Queen),
+            'R' => Some(Self::Rook),
+            'B' => Some(Self::Bishop),
+            'N' => Some(Self::Knight),
+            _ => None,
+        }
+    }
+
+    /// Bishops, rooks and queens, which attack along rays.
+    pub fn is_slider(&self) -> bool {
+        matches!(self, Self::Bishop | Self::Rook | Self::Queen)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
     pub color: PieceColor,
     pub kind: PieceType,
 }
 
+impl Piece {
+    pub const fn new(color: PieceColor, kind: PieceType) -> Self {
+        Self { color, kind }
+    }
+
+    pub const fn white_king() -> Self {
+        Self::new(PieceColor::White, PieceType::King)
+    }
+
+    pub const fn white_queen() -> Self {
+        Self::new(PieceColor::White, PieceType::Queen)
+    }
+
+    pub const fn white_rook() -> Self {
+        Self::new(PieceColor::White, PieceType::Rook)
+    }
+
+    pub const fn white_bishop() -> Self {
+        Self::new(PieceColor::White, PieceType::Bishop)
+    }
+
+    pub const fn white_knight() -> Self {
+        Self::new(PieceColor::White, PieceType::Knight)
+    }
+
+    pub const fn white_pawn() -> Self {
+        Self::new(PieceColor::White, PieceType::Pawn)
+    }
+
+    pub const fn black_king() -> Self {
+        Self::new(PieceColor::Black, PieceType::King)
+    }
+
+    pub const fn black_queen() -> Self {
+        Self::new(PieceColor::Black, PieceType::Queen)
+    }
+
+    pub const fn black_rook() -> Self {
+        Self::new(PieceColor::Black, PieceType::Rook)
+    }
+
+    pub const fn black_bishop() -> Self {
+        Self::new(PieceColor::Black, PieceType::Bishop)
+    }
+
+    pub const fn black_knight() -> Self {
+        Self::new(PieceColor::Black, PieceType::Knight)
+    }
+
+    pub const fn black_pawn() -> Self {
+        Self::new(PieceColor::Black, PieceType::Pawn)
+    }
+}
+
 impl std::fmt::Display for PieceType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -66,3 +143,21 @@ impl std::fmt::Display for PieceType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shorthand() {
+        let knight = Piece {
+            kind: PieceType::Knight,
+            color: PieceColor::White,
+        };
+        assert_eq!(Piece::new(PieceColor::White, PieceType::Knight), knight);
+        assert_eq!(Piece::white_knight(), knight);
+        assert_ne!(Piece::black_knight(), knight);
+        assert_eq!(Piece::black_king().color, PieceColor::Black);
+        assert_eq!(Piece::black_king().kind, PieceType::King);
+    }
+}
@@ -1,8 +1,10 @@
+use std::fmt;
 use std::ops::Add;
 use std::ops::Sub;
-use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum File {
     A,
     B,
@@ -30,6 +32,22 @@ impl fmt::Display for File {
 }
 
 impl File {
+    /// Files from a to h.
+    pub fn all() -> [File; 8] {
+        use File::*;
+        [A, B, C, D, E, F, G, H]
+    }
+
+    /// The file to the right, towards h.
+    pub fn next(self) -> Option<Self> {
+        Self::try_from_u8(self.as_u8() + 1)
+    }
+
+    /// The file to the left, towards a.
+    pub fn prev(self) -> Option<Self> {
+        Self::try_from_u8(self.as_u8() - 1)
+    }
+
     pub fn as_u8(self) -> u8 {
         use File::*;
         match self {
@@ -62,6 +80,21 @@ impl File {
     pub fn from_u8(value: u8) -> Self {
         Self::try_from_u8(value).unwrap()
     }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        use File::*;
+        match c {
+            'a' => Some(A),
+            'b' => Some(B),
+            'c' => Some(C),
+            'd' => Some(D),
+            'e' => Some(E),
+            'f' => Some(F),
+            'g' => Some(G),
+            'h' => Some(H),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq<u8> for File {
@@ -102,6 +135,8 @@ impl Sub<i8> for File {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8"))]
 pub struct Rank(u8);
 
 impl Rank {
@@ -121,18 +156,33 @@ impl Rank {
         self.0
     }
 
+    /// Ranks from 1 to 8.
+    pub fn all() -> [Rank; 8] {
+        [Rank(1), Rank(2), Rank(3), Rank(4), Rank(5), Rank(6), Rank(7), Rank(8)]
+    }
+
     pub fn map<F: Fn(u8) -> u8>(&self, f: F) -> Self {
         let new = f(self.0);
         Self::new(new)
     }
 }
 
+impl TryFrom<u8> for Rank {
+    type Error = String;
+
+    fn try_from(rank: u8) -> Result<Self, Self::Error> {
+        Self::try_new(rank).ok_or_else(|| format!("invalid rank {rank}"))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct UnboundedPos {
     pub file: i8,
     pub rank: i8,
 }
 
+/// Shifts saturate, so moving far off the board still gives a position
+/// for which `to_pos` returns `None`.
 impl UnboundedPos {
     pub fn from_pos(pos: Pos) -> Self {
         Self {
@@ -143,28 +193,28 @@ impl UnboundedPos {
 
     pub fn up(&self, n: u8) -> Self {
         Self {
-            rank: self.rank + n as i8,
+            rank: self.rank.saturating_add_unsigned(n),
             ..*self
         }
     }
 
     pub fn down(&self, n: u8) -> Self {
         Self {
-            rank: self.rank - n as i8,
+            rank: self.rank.saturating_sub_unsigned(n),
             ..*self
         }
     }
 
     pub fn left(&self, n: u8) -> Self {
         Self {
-            file: self.file - n as i8,
+            file: self.file.saturating_sub_unsigned(n),
             ..*self
         }
     }
 
     pub fn right(&self, n: u8) -> Self {
         Self {
-            file: self.file + n as i8,
+            file: self.file.saturating_add_unsigned(n),
             ..*self
         }
     }
@@ -253,11 +303,62 @@ impl fmt::Display for Pos {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsePosError;
+
+impl fmt::Display for ParsePosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid square, expected something like \"e4\"")
+    }
+}
+
+impl FromStr for Pos {
+    type Err = ParsePosError;
+
+    /// Parses algebraic notation like `e4`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let (file, rank) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(file), Some(rank), None) => (file, rank),
+            _ => return Err(ParsePosError),
+        };
+        let file = File::from_char(file).ok_or(ParsePosError)?;
+        let rank = rank
+            .to_digit(10)
+            .and_then(|r| Rank::try_new(r as u8))
+            .ok_or(ParsePosError)?;
+        Ok(Pos::new(file, rank))
+    }
+}
+
+/// Serialized as a string like `"e4"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Pos {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Pos {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 impl Pos {
     pub fn new(file: File, rank: Rank) -> Self {
         Self { file, rank }
     }
 
+    /// All squares of the board, rank by rank: a1, b1, ..., h1, a2, ..., h8.
+    pub fn all() -> impl Iterator<Item = Pos> {
+        Rank::all()
+            .into_iter()
+            .flat_map(|rank| File::all().into_iter().map(move |file| Pos::new(file, rank)))
+    }
+
     pub fn up(&self, n: u8) -> Self {
         self.with_rank(|r| r.map(|r| r + n))
     }
@@ -266,12 +367,15 @@ impl Pos {
         self.with_rank(|r| r.map(|r| r - n))
     }
 
+    /// Panics if the square is off the board, see `UnboundedPos` for steps that may
+    /// leave it.
     pub fn left(&self, n: u8) -> Self {
-        self.with_file(|f| f - 1)
+        self.with_file(|f| f - n as i8)
     }
 
+    /// Panics if the square is off the board.
     pub fn right(&self, n: u8) -> Self {
-        self.with_file(|f| f + 1)
+        self.with_file(|f| f + n as i8)
     }
 
     pub fn with_rank<F>(&self, f: F) -> Self
@@ -293,5 +397,143 @@ impl Pos {
             rank: self.rank,
         }
     }
+
+    /// One-square step `(file, rank)` leading from `self` towards `other` along a rank,
+    /// file or diagonal, `None` if they aren't aligned or are the same square.
+    pub fn direction_to(&self, other: Pos) -> Option<(i8, i8)> {
+        let file = other.file.as_u8() as i8 - self.file.as_u8() as i8;
+        let rank = other.rank.get() as i8 - self.rank.get() as i8;
+        let aligned = file == 0 || rank == 0 || file.abs() == rank.abs();
+        if !aligned || (file == 0 && rank == 0) {
+            return None;
+        }
+        Some((file.signum(), rank.signum()))
+    }
+
+    /// The squares strictly between `a` and `b`, empty if they aren't on one line.
+    pub fn between(a: Pos, b: Pos) -> Vec<Pos> {
+        let (file_step, rank_step) = match a.direction_to(b) {
+            Some(step) => step,
+            None => return Vec::new(),
+        };
+        let start = UnboundedPos::from_pos(a);
+        (1..)
+            .map(|i| UnboundedPos {
+                file: start.file + file_step * i,
+                rank: start.rank + rank_step * i,
+            })
+            .map_while(|pos| pos.to_pos())
+            .take_while(|pos| *pos != b)
+            .collect()
+    }
+}
+
+/// Index of a square in the board's `squares`, row by row from the top-left corner
+/// as seen by White: a8 is 0, h8 is 7, a1 is 56 and h1 is 63.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SquareIndex(u8);
+
+impl SquareIndex {
+    pub fn get(self) -> usize {
+        self.0 as usize
+    }
 }
 
+impl From<Pos> for SquareIndex {
+    fn from(pos: Pos) -> Self {
+        Self((8 - pos.rank.get()) * 8 + pos.file.as_u8() - 1)
+    }
+}
+
+impl From<SquareIndex> for Pos {
+    fn from(index: SquareIndex) -> Self {
+        let file = File::from_u8(index.0 % 8 + 1);
+        let rank = Rank::new(8 - index.0 / 8);
+        Pos::new(file, rank)
+    }
+}
+
+impl TryFrom<u8> for SquareIndex {
+    type Error = String;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        if index < 64 {
+            Ok(Self(index))
+        } else {
+            Err(format!("invalid square index {index}"))
+        }
+    }
+}
+
+impl TryFrom<usize> for SquareIndex {
+    type Error = String;
+
+    fn try_from(index: usize) -> Result<Self, Self::Error> {
+        u8::try_from(index)
+            .map_err(|_| format!("invalid square index {index}"))
+            .and_then(Self::try_from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_indices() {
+        let cases = [("a8", 0u8), ("h8", 7), ("e4", 36), ("a1", 56), ("h1", 63), ("d5", 27)];
+        for (square, index) in cases {
+            let pos: Pos = square.parse().unwrap();
+            let square_index = SquareIndex::try_from(index).unwrap();
+            assert_eq!(SquareIndex::from(pos), square_index);
+            assert_eq!(square_index.get(), index as usize);
+            assert_eq!(Pos::from(square_index), pos);
+        }
+        assert!(SquareIndex::try_from(64u8).is_err());
+        assert!(SquareIndex::try_from(300usize).is_err());
+        for pos in Pos::all() {
+            assert_eq!(Pos::from(SquareIndex::from(pos)), pos);
+        }
+    }
+
+    #[test]
+    fn large_shifts() {
+        let e4 = UnboundedPos::from_pos(Pos::new(File::E, Rank::new(4)));
+        assert_eq!(e4.up(200).to_pos(), None);
+        assert_eq!(e4.down(u8::MAX).to_pos(), None);
+        assert_eq!(e4.right(127).to_pos(), None);
+        assert_eq!(e4.up(4).to_pos(), Some(Pos::new(File::E, Rank::new(8))));
+    }
+
+    #[test]
+    fn squares_between() {
+        let pos = |s: &str| -> Pos { s.parse().unwrap() };
+        assert_eq!(Pos::between(pos("e1"), pos("h1")), vec![pos("f1"), pos("g1")]);
+        assert_eq!(Pos::between(pos("a8"), pos("a5")), vec![pos("a7"), pos("a6")]);
+        assert_eq!(Pos::between(pos("c1"), pos("f4")), vec![pos("d2"), pos("e3")]);
+        assert_eq!(Pos::between(pos("e1"), pos("f1")), vec![]);
+        assert_eq!(Pos::between(pos("b1"), pos("c3")), vec![]);
+        assert_eq!(Pos::between(pos("d4"), pos("d4")), vec![]);
+    }
+
+    #[test]
+    fn files_and_ranks() {
+        use File::*;
+        assert_eq!(File::all(), [A, B, C, D, E, F, G, H]);
+        assert_eq!(File::H.next(), None);
+        assert_eq!(File::A.prev(), None);
+        assert_eq!(File::D.next(), Some(E));
+        assert_eq!(File::D.prev(), Some(C));
+        let ranks: Vec<u8> = Rank::all().iter().map(Rank::get).collect();
+        assert_eq!(ranks, [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn all_squares() {
+        let all: Vec<_> = Pos::all().collect();
+        assert_eq!(all.len(), 64);
+        assert_eq!(all[0], Pos::new(File::A, Rank::new(1)));
+        assert_eq!(all[1], Pos::new(File::B, Rank::new(1)));
+        assert_eq!(all[63], Pos::new(File::H, Rank::new(8)));
+    }
+}
@@ -0,0 +1,137 @@
+//! Tactics training: find the moves of a stored solution line.
+
+use crate::board::BoardState;
+use crate::moves::internal::Move;
+use crate::piece::PieceColor;
+
+/// A position with the line solving it. The line alternates the solver's moves and the
+/// opponent's replies, starting and ending with the solver's.
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    state: BoardState,
+    solver: PieceColor,
+    solution: Vec<Move>,
+    /// Number of solution moves played so far.
+    progress: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleOutcome {
+    /// The move was right, the opponent answers with `reply`.
+    Correct { reply: Move },
+    /// The move was wrong, the puzzle stays where it was.
+    Incorrect,
+    /// The move was the last one of the solution.
+    Solved,
+}
+
+impl Puzzle {
+    pub fn new(state: BoardState, solution: Vec<Move>) -> Self {
+        Self {
+            solver: state.side_to_move(),
+            state,
+            solution,
+            progress: 0,
+        }
+    }
+
+    /// Reads the solution as UCI moves separated by spaces or commas. `None` if the FEN
+    /// is invalid or a move is illegal.
+    pub fn from_uci(fen: &str, solution: &str) -> Option<Self> {
+        let start = BoardState::from_fen(fen).ok()?;
+        let mut state = start.clone();
+        let mut moves = Vec::new();
+        for uci in solution.split([' ', ',']).filter(|s| !s.is_empty()) {
+            let mv = state.from_uci(uci)?;
+            state.make_move(mv);
+            moves.push(mv);
+        }
+        Some(Self::new(start, moves))
+    }
+
+    /// The current position of the puzzle.
+    pub fn state(&self) -> &BoardState {
+        &self.state
+    }
+
+    /// The side finding the solution.
+    pub fn solver(&self) -> PieceColor {
+        self.solver
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.progress >= self.solution.len()
+    }
+}
+
+/// Checks `mv` against the next move of the solution, playing it and the opponent's reply
+/// if it's right. Any legal mate is accepted, since mating puzzles often have more than one.
+pub fn submit_move(puzzle: &mut Puzzle, mv: Move) -> PuzzleOutcome {
+    let expected = match puzzle.solution.get(puzzle.progress) {
+        Some(expected) => *expected,
+        None => return PuzzleOutcome::Incorrect,
+    };
+    if !puzzle.state.all_legal_moves().contains(&mv) {
+        return PuzzleOutcome::Incorrect;
+    }
+    let mut next = puzzle.state.clone();
+    next.make_move(mv);
+    let is_mate = next.is_checkmate(next.side_to_move());
+    if mv != expected && !is_mate {
+        return PuzzleOutcome::Incorrect;
+    }
+    puzzle.state = next;
+    puzzle.progress += 1;
+    let reply = puzzle.solution.get(puzzle.progress).copied();
+    match reply.filter(|_| !is_mate) {
+        Some(reply) => {
+            puzzle.state.make_move(reply);
+            puzzle.progress += 1;
+            PuzzleOutcome::Correct { reply }
+        }
+        None => {
+            puzzle.progress = puzzle.solution.len();
+            PuzzleOutcome::Solved
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_rank_puzzle() {
+        let fen = "3r2k1/5ppp/8/8/8/8/4R3/4R1K1 w - - 0 1";
+        let mut puzzle = Puzzle::from_uci(fen, "e2e8 d8e8 e1e8").unwrap();
+        assert_eq!(puzzle.solver(), PieceColor::White);
+        let wrong = puzzle.state().from_uci("e2e7").unwrap();
+        assert_eq!(submit_move(&mut puzzle, wrong), PuzzleOutcome::Incorrect);
+        assert_eq!(puzzle.state().to_fen(), fen);
+        let first = puzzle.state().from_uci("e2e8").unwrap();
+        let reply = Move::new("d8".parse().unwrap(), "e8".parse().unwrap());
+        assert_eq!(
+            submit_move(&mut puzzle, first),
+            PuzzleOutcome::Correct { reply }
+        );
+        assert_eq!(puzzle.solver(), PieceColor::White);
+        assert!(!puzzle.is_solved());
+        let mate = puzzle.state().from_uci("e1e8").unwrap();
+        assert_eq!(submit_move(&mut puzzle, mate), PuzzleOutcome::Solved);
+        assert!(puzzle.is_solved());
+        assert!(Puzzle::from_uci(fen, "e2e8 e1e8").is_none());
+    }
+
+    #[test]
+    fn illegal_mate() {
+        // The rook is pinned to its king, so Rd8 would mate but can't be played.
+        let fen = "7k/6pp/7b/8/8/8/3R4/2K5 w - - 0 1";
+        let mut puzzle = Puzzle::from_uci(fen, "c1b1").unwrap();
+        let pinned_mate = Move::new("d2".parse().unwrap(), "d8".parse().unwrap());
+        assert_eq!(
+            submit_move(&mut puzzle, pinned_mate),
+            PuzzleOutcome::Incorrect
+        );
+        assert_eq!(puzzle.state().to_fen(), fen);
+    }
+}
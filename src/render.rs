@@ -0,0 +1,416 @@
+//! Drawing positions as standalone SVG documents, independent from the UI.
+
+use std::fmt::Write;
+
+use crate::board::BoardState;
+use crate::moves::internal::Move;
+use crate::piece::PieceColor;
+use crate::pos::Pos;
+
+/// Size of a square in percents of the board size.
+pub const SQUARE_SIZE: f32 = 12.5;
+
+/// Column and row of the square as drawn, a8 is in the top-left corner.
+pub fn square_coords(pos: Pos) -> (i32, i32) {
+    let x = (pos.file.as_u8() - 1) as i32;
+    let y = (8 - pos.rank.get()) as i32;
+    (x, y)
+}
+
+/// Which side is drawn at the bottom of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoardOrientation {
+    #[default]
+    White,
+    Black,
+    /// Follows the side to move, for pass-and-play on one screen.
+    SideToMove,
+}
+
+impl BoardOrientation {
+    /// The color at the bottom of the board when `side_to_move` is to move.
+    pub fn bottom(self, side_to_move: PieceColor) -> PieceColor {
+        match self {
+            Self::White => PieceColor::White,
+            Self::Black => PieceColor::Black,
+            Self::SideToMove => side_to_move,
+        }
+    }
+}
+
+/// Like [`square_coords`], but rotated so that `bottom`'s first rank is drawn at the bottom.
+pub fn oriented_square_coords(pos: Pos, bottom: PieceColor) -> (i32, i32) {
+    let (x, y) = square_coords(pos);
+    match bottom {
+        PieceColor::White => (x, y),
+        PieceColor::Black => (7 - x, 7 - y),
+    }
+}
+
+/// Length of an arrow head in percent of the board size.
+pub const ARROW_HEAD: f32 = 3.0;
+
+/// Centre of the square as drawn, in percent of the board size.
+pub fn square_center(pos: Pos, bottom: PieceColor) -> (f32, f32) {
+    let (x, y) = oriented_square_coords(pos, bottom);
+    let center = |c: i32| c as f32 * SQUARE_SIZE + SQUARE_SIZE / 2.0;
+    (center(x), center(y))
+}
+
+/// Ends of the shaft of an arrow between two squares, in percent of the board size.
+/// The shaft stops [`ARROW_HEAD`] short of the target's centre, where the head ends.
+pub fn arrow_endpoints(from: Pos, to: Pos, bottom: PieceColor) -> ((f32, f32), (f32, f32)) {
+    let start = square_center(from, bottom);
+    let end = square_center(to, bottom);
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return (start, end);
+    }
+    let shorten = ARROW_HEAD / length;
+    (start, (end.0 - dx * shorten, end.1 - dy * shorten))
+}
+
+/// CSS colors used to draw the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardTheme {
+    pub light: String,
+    pub dark: String,
+    /// Legal move hints.
+    pub hint: String,
+    /// Outline of the selected piece.
+    pub selection: String,
+    /// Outline of the squares of a queued premove.
+    pub premove: String,
+}
+
+impl BoardTheme {
+    pub fn blue() -> Self {
+        Self {
+            light: "rgb(245,245,245)".to_string(),
+            dark: "rgb(176,224,230)".to_string(),
+            hint: "red".to_string(),
+            selection: "yellow".to_string(),
+            premove: "blue".to_string(),
+        }
+    }
+
+    pub fn green() -> Self {
+        Self {
+            light: "rgb(238,238,210)".to_string(),
+            dark: "rgb(118,150,86)".to_string(),
+            hint: "rgb(20,85,30)".to_string(),
+            selection: "rgb(246,246,105)".to_string(),
+            premove: "rgb(20,30,85)".to_string(),
+        }
+    }
+
+    pub fn brown() -> Self {
+        Self {
+            light: "rgb(240,217,181)".to_string(),
+            dark: "rgb(181,136,99)".to_string(),
+            hint: "rgb(100,111,64)".to_string(),
+            selection: "rgb(205,210,106)".to_string(),
+            premove: "rgb(20,30,85)".to_string(),
+        }
+    }
+
+    pub fn presets() -> [(&'static str, BoardTheme); 3] {
+        [
+            ("Blue", Self::blue()),
+            ("Green", Self::green()),
+            ("Brown", Self::brown()),
+        ]
+    }
+}
+
+impl Default for BoardTheme {
+    fn default() -> Self {
+        Self::blue()
+    }
+}
+
+/// How highlighted squares and capture hints are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightStyle {
+    /// Fill the shape instead of outlining it.
+    pub filled: bool,
+    /// Opacity in percents.
+    pub opacity: u8,
+    /// Outline width in pixels, unused if filled.
+    pub stroke_width: u8,
+}
+
+impl HighlightStyle {
+    pub fn outlined() -> Self {
+        Self {
+            filled: false,
+            opacity: 100,
+            stroke_width: 3,
+        }
+    }
+
+    pub fn filled() -> Self {
+        Self {
+            filled: true,
+            opacity: 50,
+            stroke_width: 0,
+        }
+    }
+
+    pub fn faint() -> Self {
+        Self {
+            opacity: 40,
+            ..Self::outlined()
+        }
+    }
+
+    pub fn presets() -> [(&'static str, HighlightStyle); 3] {
+        [
+            ("Outlined", Self::outlined()),
+            ("Filled", Self::filled()),
+            ("Faint", Self::faint()),
+        ]
+    }
+
+    /// The SVG `style` attribute of a highlight drawn in `color`.
+    pub fn css(&self, color: &str) -> String {
+        let opacity = f32::from(self.opacity.min(100)) / 100.0;
+        if self.filled {
+            format!("fill:{color};fill-opacity:{opacity};stroke-width:0")
+        } else {
+            format!(
+                "fill-opacity:0;stroke:{color};stroke-width:{width};stroke-opacity:{opacity}",
+                width = self.stroke_width
+            )
+        }
+    }
+}
+
+impl Default for HighlightStyle {
+    fn default() -> Self {
+        Self::outlined()
+    }
+}
+
+/// Fill color of a square of the given [`square_color`].
+pub fn square_fill(theme: &BoardTheme, color: PieceColor) -> &str {
+    match color {
+        PieceColor::White => &theme.light,
+        PieceColor::Black => &theme.dark,
+    }
+}
+
+pub fn square_color(x: i32, y: i32) -> PieceColor {
+    if (x + y) % 2 == 0 {
+        PieceColor::White
+    } else {
+        PieceColor::Black
+    }
+}
+
+pub fn render_ply_svg(state: &BoardState) -> String {
+    let theme = BoardTheme::default();
+    let mut svg =
+        String::from(r#"<svg xmlns="http://www.w3.org/2000/svg" width="512" height="512">"#);
+    for pos in Pos::all() {
+        let (x, y) = square_coords(pos);
+        let fill = square_fill(&theme, square_color(x, y));
+        let _ = write!(
+            svg,
+            r#"<rect x="{x}%" y="{y}%" width="{SQUARE_SIZE}%" height="{SQUARE_SIZE}%" style="fill:{fill}"/>"#,
+            x = x as f32 * SQUARE_SIZE,
+            y = y as f32 * SQUARE_SIZE,
+        );
+    }
+    for (piece, pos) in state.pieces() {
+        let (x, y) = square_coords(pos);
+        let href = state.handles.get(&piece).unwrap_or_default();
+        let _ = write!(
+            svg,
+            r#"<image x="{x}%" y="{y}%" width="{SQUARE_SIZE}%" height="{SQUARE_SIZE}%" href="{href}"/>"#,
+            x = x as f32 * SQUARE_SIZE,
+            y = y as f32 * SQUARE_SIZE,
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// The piece images by their [`Handles`](crate::board::Handles) path, built into the
+/// binary so PNGs don't depend on the directory it runs from.
+#[cfg(feature = "png")]
+const PIECE_SVGS: [(&str, &str); 12] = [
+    ("./resources/wP.svg", include_str!("../resources/wP.svg")),
+    ("./resources/wN.svg", include_str!("../resources/wN.svg")),
+    ("./resources/wB.svg", include_str!("../resources/wB.svg")),
+    ("./resources/wR.svg", include_str!("../resources/wR.svg")),
+    ("./resources/wQ.svg", include_str!("../resources/wQ.svg")),
+    ("./resources/wK.svg", include_str!("../resources/wK.svg")),
+    ("./resources/bP.svg", include_str!("../resources/bP.svg")),
+    ("./resources/bN.svg", include_str!("../resources/bN.svg")),
+    ("./resources/bB.svg", include_str!("../resources/bB.svg")),
+    ("./resources/bR.svg", include_str!("../resources/bR.svg")),
+    ("./resources/bQ.svg", include_str!("../resources/bQ.svg")),
+    ("./resources/bK.svg", include_str!("../resources/bK.svg")),
+];
+
+/// Rasterizes [`render_ply_svg`] into a PNG image `size` pixels wide and high.
+#[cfg(feature = "png")]
+pub fn render_png(state: &BoardState, size: u32) -> Vec<u8> {
+    use resvg::{tiny_skia, usvg};
+    let resolve_string: usvg::ImageHrefStringResolverFn = Box::new(|href, _| {
+        let (_, svg) = PIECE_SVGS.iter().find(|(path, _)| *path == href)?;
+        let piece = usvg::Tree::from_str(svg, &usvg::Options::default()).ok()?;
+        Some(usvg::ImageKind::SVG(piece))
+    });
+    let options = usvg::Options {
+        image_href_resolver: usvg::ImageHrefResolver {
+            resolve_string,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let tree =
+        usvg::Tree::from_str(&render_ply_svg(state), &options).expect("the board SVG is valid");
+    let mut pixmap = tiny_skia::Pixmap::new(size, size).expect("the size isn't zero");
+    let scale = size as f32 / tree.size().width();
+    let transform = tiny_skia::Transform::from_scale(scale, scale);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    pixmap.encode_png().expect("the pixmap can be encoded")
+}
+
+/// Renders the starting position and the position after every ply of the game.
+pub fn render_game_svgs(moves: &[Move]) -> Vec<String> {
+    let mut state = BoardState::new();
+    let mut frames = vec![render_ply_svg(&state)];
+    for mv in moves {
+        state.make_move(*mv);
+        frames.push(render_ply_svg(&state));
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pos::{File, Rank};
+
+    #[test]
+    fn theme_fill() {
+        let theme = BoardTheme::brown();
+        // a1 is dark, h1 is light.
+        let (x, y) = square_coords(Pos::new(File::A, Rank::new(1)));
+        assert_eq!(square_fill(&theme, square_color(x, y)), "rgb(181,136,99)");
+        let (x, y) = square_coords(Pos::new(File::H, Rank::new(1)));
+        assert_eq!(square_fill(&theme, square_color(x, y)), "rgb(240,217,181)");
+        let green = BoardTheme::green();
+        assert_eq!(square_fill(&green, PieceColor::Black), green.dark);
+    }
+
+    #[test]
+    fn highlight_css() {
+        assert_eq!(
+            HighlightStyle::outlined().css("red"),
+            "fill-opacity:0;stroke:red;stroke-width:3;stroke-opacity:1"
+        );
+        assert_eq!(
+            HighlightStyle::filled().css("red"),
+            "fill:red;fill-opacity:0.5;stroke-width:0"
+        );
+        let too_opaque = HighlightStyle {
+            opacity: 250,
+            ..HighlightStyle::faint()
+        };
+        assert!(too_opaque.css("red").ends_with("stroke-opacity:1"));
+    }
+
+    #[test]
+    fn orientation_follows_side_to_move() {
+        let e2 = Pos::new(File::E, Rank::new(2));
+        let e4 = Pos::new(File::E, Rank::new(4));
+        let mut state = BoardState::new();
+        let orientation = BoardOrientation::SideToMove;
+        let bottom = orientation.bottom(state.side_to_move());
+        assert_eq!(oriented_square_coords(e2, bottom), (4, 6));
+        state.make_move(Move::new(e2, e4));
+        let bottom = orientation.bottom(state.side_to_move());
+        assert_eq!(bottom, PieceColor::Black);
+        assert_eq!(oriented_square_coords(e4, bottom), (3, 3));
+        assert_eq!(
+            BoardOrientation::White.bottom(state.side_to_move()),
+            PieceColor::White
+        );
+    }
+
+    #[test]
+    fn arrows() {
+        let e2 = Pos::new(File::E, Rank::new(2));
+        let e4 = Pos::new(File::E, Rank::new(4));
+        let g1 = Pos::new(File::G, Rank::new(1));
+        let f3 = Pos::new(File::F, Rank::new(3));
+        assert_eq!(
+            arrow_endpoints(e2, e4, PieceColor::White),
+            ((56.25, 81.25), (56.25, 59.25))
+        );
+        assert_eq!(
+            arrow_endpoints(e2, e4, PieceColor::Black),
+            ((43.75, 18.75), (43.75, 40.75))
+        );
+        let ((x1, y1), (x2, y2)) = arrow_endpoints(g1, f3, PieceColor::White);
+        assert_eq!((x1, y1), (81.25, 93.75));
+        let (tip_x, tip_y) = square_center(f3, PieceColor::White);
+        assert!(((tip_x - x2).hypot(tip_y - y2) - ARROW_HEAD).abs() < 1e-4);
+    }
+
+    #[test]
+    fn svg_document() {
+        let svg = render_ply_svg(&BoardState::new());
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>"));
+        // Everything except the root element is self-closing.
+        assert_eq!(svg.matches('<').count(), svg.matches("/>").count() + 2);
+        assert_eq!(svg.matches("<rect ").count(), 64);
+        assert_eq!(svg.matches("<image ").count(), 32);
+    }
+
+    #[test]
+    fn game_frames() {
+        let d2 = Pos::new(File::D, Rank::new(2));
+        let d4 = Pos::new(File::D, Rank::new(4));
+        let e7 = Pos::new(File::E, Rank::new(7));
+        let e5 = Pos::new(File::E, Rank::new(5));
+        let frames = render_game_svgs(&[Move::new(d2, d4), Move::new(e7, e5), Move::new(d4, e5)]);
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[3].matches("<image ").count(), 31);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_image() {
+        let png = render_png(&BoardState::new(), 256);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        // The IHDR chunk comes first, starting with the width and height.
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 256);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 256);
+        // A piece covers the center of its square, an empty square is a single color.
+        let pixmap = resvg::tiny_skia::Pixmap::decode_png(&png).unwrap();
+        let pixel = |x, y| pixmap.pixel(x, y).unwrap();
+        let square = |file: u32, rank: u32| (file * 32, (8 - rank) * 32);
+        let (x, y) = square(4, 1);
+        assert_ne!(
+            pixel(x + 16, y + 16),
+            pixel(x + 1, y + 1),
+            "white king on e1"
+        );
+        let (x, y) = square(3, 8);
+        assert_ne!(
+            pixel(x + 16, y + 16),
+            pixel(x + 1, y + 1),
+            "black queen on d8"
+        );
+        let (x, y) = square(4, 4);
+        assert_eq!(pixel(x + 16, y + 16), pixel(x + 1, y + 1), "empty e4");
+    }
+}
@@ -0,0 +1,19 @@
+use yew::prelude::*;
+
+use chess::board::GameResult;
+
+#[derive(Debug, PartialEq, Properties)]
+pub struct Props {
+    pub result: GameResult,
+    pub on_new_game: Callback<MouseEvent>,
+}
+
+#[function_component(ResultBanner)]
+pub fn result_banner(props: &Props) -> Html {
+    html! {
+        <div class={classes!("flex", "flex-row", "items-center", "gap-4", "p-2")}>
+            <span>{ props.result.description() }</span>
+            <button onclick={props.on_new_game.clone()}>{ "New Game" }</button>
+        </div>
+    }
+}
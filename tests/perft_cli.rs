@@ -0,0 +1,44 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::process::Command;
+
+use chess::fen::KnownPosition;
+
+#[test]
+fn start_position_divide() {
+    let output = Command::new(env!("CARGO_BIN_EXE_perft"))
+        .args([KnownPosition::Starting.fen(), "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 22);
+    assert!(lines.contains(&"e2e4: 20"));
+    assert!(lines.contains(&"g1f3: 20"));
+    assert_eq!(lines[20], "");
+    assert_eq!(lines[21], "Nodes searched: 400");
+}
+
+#[test]
+fn promotions_divide() {
+    // Black's promotions on a1 and b1 are made at depth 2, they must not print anything.
+    let output = Command::new(env!("CARGO_BIN_EXE_perft"))
+        .args([
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "3",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 8);
+    for line in &lines[..6] {
+        let (mv, nodes) = line.split_once(": ").unwrap();
+        assert!(mv.len() == 4 || mv.len() == 5, "{line}");
+        assert!(nodes.parse::<u64>().is_ok(), "{line}");
+    }
+    assert_eq!(lines[6], "");
+    assert_eq!(lines[7], "Nodes searched: 9467");
+}
@@ -0,0 +1,46 @@
+use chess::board::BoardState;
+
+/// `(fen, depth, nodes)` from the perft results page of the Chess Programming Wiki.
+/// Together the positions cover castling, en passant, promotions, pins and checks.
+const SUITE: &[(&str, u32, u64)] = &[
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        4,
+        197_281,
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        3,
+        97_862,
+    ),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43_238),
+    (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        3,
+        9_467,
+    ),
+    // The same position with the colors swapped.
+    (
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        3,
+        9_467,
+    ),
+    (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        3,
+        62_379,
+    ),
+    (
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        3,
+        89_890,
+    ),
+];
+
+#[test]
+fn perft_suite() {
+    for &(fen, depth, nodes) in SUITE {
+        let state = BoardState::from_fen(fen).unwrap_or_else(|err| panic!("{fen}: {err}"));
+        assert_eq!(state.perft(depth), nodes, "{fen} at depth {depth}");
+    }
+}